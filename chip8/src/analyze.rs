@@ -0,0 +1,130 @@
+//! Static ROM analysis, see `analyze_rom`
+
+use crate::opcode::{decode, Opcode, PATTERNS};
+use crate::quirks::Mode;
+
+/// SUPER-CHIP encodings the core doesn't decode yet: scrolling, exit, the
+/// large font and the RPL flags
+pub const UNIMPLEMENTED_SCHIP: [&str; 7] = ["00Cn", "00FB", "00FC", "00FD", "Fx30", "Fx75", "Fx85"];
+
+/// Index into `UNIMPLEMENTED_SCHIP` of the encoding `word` belongs to
+fn unimplemented_schip(word: u16) -> Option<usize> {
+    let index = match word {
+        0x00C0..=0x00CF => 0,
+        0x00FB => 1,
+        0x00FC => 2,
+        0x00FD => 3,
+        _ => match word & 0xF0FF {
+            0xF030 => 4,
+            0xF075 => 5,
+            0xF085 => 6,
+            _ => return None,
+        },
+    };
+    Some(index)
+}
+
+/// Opcode families used by a ROM, see `analyze_rom`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomReport {
+    /// Occurrences per family, indexed like `PATTERNS`
+    counts: [usize; PATTERNS.len()],
+    /// First occurrence per family, to check which mode it needs
+    first: [Option<Opcode>; PATTERNS.len()],
+    /// Occurrences per encoding, indexed like `UNIMPLEMENTED_SCHIP`
+    schip_counts: [usize; UNIMPLEMENTED_SCHIP.len()],
+    /// Words that aren't an instruction at all, usually data
+    unknown: usize,
+}
+
+impl RomReport {
+    /// Number of times the family at `PATTERNS[family]` occurs
+    pub fn count(&self, family: usize) -> usize {
+        self.counts[family]
+    }
+
+    /// Patterns and counts of the families that occur, in `PATTERNS` order
+    /// followed by the `UNIMPLEMENTED_SCHIP` ones
+    pub fn families(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        PATTERNS
+            .iter()
+            .zip(self.counts.iter())
+            .chain(UNIMPLEMENTED_SCHIP.iter().zip(self.schip_counts.iter()))
+            .filter(|(_, &count)| count > 0)
+            .map(|(&pattern, &count)| (pattern, count))
+    }
+
+    /// Words that are no instruction at all, usually data
+    pub fn unknown(&self) -> usize {
+        self.unknown
+    }
+
+    /// Patterns of the SUPER-CHIP families that occur but that the core
+    /// can't execute yet
+    pub fn unimplemented(&self) -> impl Iterator<Item = &'static str> + '_ {
+        UNIMPLEMENTED_SCHIP
+            .iter()
+            .zip(self.schip_counts.iter())
+            .filter(|(_, &count)| count > 0)
+            .map(|(&pattern, _)| pattern)
+    }
+
+    /// Patterns of the families that occur but don't exist in `mode`
+    pub fn unavailable_in(&self, mode: Mode) -> impl Iterator<Item = &'static str> + '_ {
+        let schip = self.unimplemented().filter(move |_| mode == Mode::Chip8);
+        self.first
+            .iter()
+            .flatten()
+            .filter(move |opcode| !opcode.available_in(mode))
+            .map(|opcode| opcode.pattern())
+            .chain(schip)
+    }
+
+    /// Smallest instruction set with every family that occurs
+    pub fn required_mode(&self) -> Mode {
+        [Mode::Chip8, Mode::SuperChip]
+            .iter()
+            .copied()
+            .find(|&mode| self.unavailable_in(mode).next().is_none())
+            .unwrap_or(Mode::XoChip)
+    }
+}
+
+/// Count the opcode families in a ROM without running it
+///
+/// Every aligned word is decoded, so sprites and other data are counted too
+/// when they happen to look like instructions. SUPER-CHIP encodings the core
+/// can't run yet are still counted, see `unimplemented`, the rest is reported
+/// as `unknown`. The address word after F000 and a trailing odd byte are
+/// skipped. The counts are a hint for picking quirks before running an
+/// unknown ROM, not a proof of what executes.
+pub fn analyze_rom(bytes: &[u8]) -> RomReport {
+    let mut report = RomReport {
+        counts: [0; PATTERNS.len()],
+        first: [None; PATTERNS.len()],
+        schip_counts: [0; UNIMPLEMENTED_SCHIP.len()],
+        unknown: 0,
+    };
+
+    let mut words = bytes
+        .chunks_exact(2)
+        .map(|word| (word[0] as u16) << 8 | word[1] as u16);
+    while let Some(word) = words.next() {
+        match decode(word) {
+            Some(opcode) => {
+                let family = opcode.family();
+                report.counts[family] += 1;
+                report.first[family].get_or_insert(opcode);
+                if opcode == Opcode::LdILong {
+                    words.next();
+                }
+            }
+            None => match unimplemented_schip(word) {
+                Some(index) => report.schip_counts[index] += 1,
+                None => report.unknown += 1,
+            },
+        }
+    }
+
+    report
+}
@@ -0,0 +1,342 @@
+//! Assembler for the mnemonics printed by the `Opcode` formatter
+//!
+//! One instruction per line, e.g. `LD V1, 5`, `DRW V0, V1, 3` or `JMP 200`.
+//! Numbers are hex like the disassembler output, optionally prefixed with
+//! `0x`. Registers are `V0` to `V15` or `VA` to `VF`. `;` starts a comment.
+//!
+//! `assemble_program` also accepts:
+//!
+//! - labels, `name:` at the start of a line, usable wherever an address is
+//! - `DB` followed by comma separated bytes, for sprite data
+//! - `LD I, LONG nnnn`, the XO-CHIP long load with its address word
+//!
+//! `JP` is accepted for `JMP`. `JP Vx, xnn` is the BXNN form the disassembler
+//! prints with the `jump_with_vx` quirk, the register has to match the high
+//! digit of the address like in the encoding.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::opcode::Opcode;
+
+/// Programs are assembled for this address
+const ORIGIN: u16 = 0x200;
+/// Last address a program can use
+const MEMORY_END: usize = 0x1000;
+
+/// An error in the assembly source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    /// 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AssembleError {}
+
+/// Assemble a single instruction into its 16 bit encoding
+///
+/// Labels are not available, addresses must be numbers.
+pub fn assemble(line: &str) -> Result<u16, AssembleError> {
+    let no_labels = BTreeMap::new();
+    parse_instruction(strip_comment(line), &no_labels)
+        .map(|opcode| opcode.encode())
+        .map_err(|message| AssembleError { line: 1, message })
+}
+
+/// Assemble a program into a ROM image loaded at 0x200
+pub fn assemble_program(source: &str) -> Result<Vec<u8>, AssembleError> {
+    // First pass: find the address of every label
+    let mut labels = BTreeMap::new();
+    let mut addr = ORIGIN as usize;
+    for (number, line) in source.lines().enumerate() {
+        let error = |message| AssembleError {
+            line: number + 1,
+            message,
+        };
+
+        let (label, statement) = split_label(strip_comment(line));
+        if let Some(label) = label {
+            if labels.insert(label, addr as u16).is_some() {
+                return Err(error(format!("duplicate label {}", label)));
+            }
+        }
+        addr += statement_size(statement).map_err(error)?;
+        if addr > MEMORY_END {
+            return Err(error("program doesn't fit in memory".to_string()));
+        }
+    }
+
+    // Second pass: encode with every label known
+    let mut rom = Vec::new();
+    for (number, line) in source.lines().enumerate() {
+        let (_, statement) = split_label(strip_comment(line));
+        encode_statement(statement, &labels, &mut rom).map_err(|message| AssembleError {
+            line: number + 1,
+            message,
+        })?;
+    }
+
+    Ok(rom)
+}
+
+fn strip_comment(line: &str) -> &str {
+    line.split(';').next().unwrap_or("").trim()
+}
+
+fn split_label(line: &str) -> (Option<&str>, &str) {
+    match line.split_once(':') {
+        Some((label, rest)) => (Some(label.trim()), rest.trim()),
+        None => (None, line),
+    }
+}
+
+/// Split a statement into its upper case mnemonic and operands
+fn split_statement(statement: &str) -> (String, Vec<&str>) {
+    let (mnemonic, operands) = statement
+        .split_once(char::is_whitespace)
+        .unwrap_or((statement, ""));
+    let operands = operands
+        .split(',')
+        .map(str::trim)
+        .filter(|operand| !operand.is_empty())
+        .collect();
+    (mnemonic.to_ascii_uppercase(), operands)
+}
+
+fn statement_size(statement: &str) -> Result<usize, String> {
+    let (mnemonic, operands) = split_statement(statement);
+    Ok(match mnemonic.as_str() {
+        "" => 0,
+        "DB" => operands.len(),
+        _ if is_long_load(&mnemonic, &operands) => 4,
+        _ => 2,
+    })
+}
+
+fn encode_statement(
+    statement: &str,
+    labels: &BTreeMap<&str, u16>,
+    rom: &mut Vec<u8>,
+) -> Result<(), String> {
+    let (mnemonic, operands) = split_statement(statement);
+    match mnemonic.as_str() {
+        "" => {}
+        "DB" => {
+            for operand in operands {
+                let byte = number(operand)?;
+                if byte > 0xFF {
+                    return Err(format!("{} doesn't fit in a byte", operand));
+                }
+                rom.push(byte as u8);
+            }
+        }
+        _ if is_long_load(&mnemonic, &operands) => {
+            let long = operands[1]["LONG".len()..].trim();
+            let addr = address(long, labels, 0xFFFF)?;
+            rom.extend_from_slice(&[0xF0, 0x00, (addr >> 8) as u8, addr as u8]);
+        }
+        _ => {
+            let instruction = parse_instruction(statement, labels)?.encode();
+            rom.extend_from_slice(&instruction.to_be_bytes());
+        }
+    }
+    Ok(())
+}
+
+/// `LD I, LONG nnnn` with the address, `LD I, LONG` alone is a plain F000
+fn is_long_load(mnemonic: &str, operands: &[&str]) -> bool {
+    mnemonic == "LD"
+        && operands.len() == 2
+        && operands[0].eq_ignore_ascii_case("I")
+        && operands[1].len() > "LONG".len()
+        && operands[1][.."LONG".len()].eq_ignore_ascii_case("LONG")
+}
+
+fn parse_instruction(statement: &str, labels: &BTreeMap<&str, u16>) -> Result<Opcode, String> {
+    let (mnemonic, operands) = split_statement(statement);
+    let operands: Vec<Operand> = operands.into_iter().map(Operand::parse).collect();
+
+    use Operand::*;
+    let opcode = match (mnemonic.as_str(), operands.as_slice()) {
+        ("CLS", []) => Opcode::Cls,
+        ("RET", []) => Opcode::Ret,
+        ("LOW", []) => Opcode::LoRes,
+        ("HIGH", []) => Opcode::HiRes,
+        ("JMP", [a]) | ("JP", [a]) => Opcode::Jmp(a.address(labels)?),
+        ("JP", [V(0), a]) => Opcode::JpV0(a.address(labels)?),
+        ("JP", [V(x), a]) => {
+            let addr = a.address(labels)?;
+            if addr >> 8 != *x as u16 {
+                return Err(format!(
+                    "JP V{:X} needs an address from {:X}00 to {:X}ff, got {:x}",
+                    x, x, x, addr
+                ));
+            }
+            Opcode::JpV0(addr)
+        }
+        ("CALL", [a]) => Opcode::Call(a.address(labels)?),
+        ("SE", [V(x), V(y)]) => Opcode::SeVxVy { x: *x, y: *y },
+        ("SE", [V(x), b]) => Opcode::SeVxByte {
+            x: *x,
+            byte: b.byte()?,
+        },
+        ("SNE", [V(x), V(y)]) => Opcode::SneVxVy { x: *x, y: *y },
+        ("SNE", [V(x), b]) => Opcode::SneVxByte {
+            x: *x,
+            byte: b.byte()?,
+        },
+        ("LD", [V(x), V(y)]) => Opcode::LdVxVy { x: *x, y: *y },
+        ("LD", [V(x), Dt]) => Opcode::LdVxDt { x: *x },
+        ("LD", [V(x), K]) => Opcode::LdVxK { x: *x },
+        ("LD", [V(x), IndirectI]) => Opcode::LdVxI { x: *x },
+        ("LD", [V(x), b]) => Opcode::LdVxByte {
+            x: *x,
+            byte: b.byte()?,
+        },
+        ("LD", [I, Long]) => Opcode::LdILong,
+        ("LD", [I, a]) => Opcode::LdI(a.address(labels)?),
+        ("LD", [Dt, V(x)]) => Opcode::LdDtVx { x: *x },
+        ("LD", [St, V(x)]) => Opcode::LdStVx { x: *x },
+        ("LD", [F(_), V(x)]) => Opcode::LdFVx { x: *x },
+        ("LD", [B(_), V(x)]) => Opcode::LdBVx { x: *x },
+        ("LD", [IndirectI, V(x)]) => Opcode::LdIVx { x: *x },
+        ("ADD", [I, V(x)]) => Opcode::AddIVx { x: *x },
+        ("ADD", [V(x), V(y)]) => Opcode::AddVxVy { x: *x, y: *y },
+        ("ADD", [V(x), b]) => Opcode::AddVxByte {
+            x: *x,
+            byte: b.byte()?,
+        },
+        ("OR", [V(x), V(y)]) => Opcode::Or { x: *x, y: *y },
+        ("AND", [V(x), V(y)]) => Opcode::And { x: *x, y: *y },
+        ("XOR", [V(x), V(y)]) => Opcode::Xor { x: *x, y: *y },
+        ("SUB", [V(x), V(y)]) => Opcode::Sub { x: *x, y: *y },
+        ("SHR", [V(x), V(y)]) => Opcode::Shr { x: *x, y: *y },
+        ("SUBN", [V(x), V(y)]) => Opcode::Subn { x: *x, y: *y },
+        ("SHL", [V(x), V(y)]) => Opcode::Shl { x: *x, y: *y },
+        ("RND", [V(x), b]) => Opcode::Rnd {
+            x: *x,
+            byte: b.byte()?,
+        },
+        ("DRW", [V(x), V(y), n]) => Opcode::Drw {
+            x: *x,
+            y: *y,
+            n: n.nibble()?,
+        },
+        ("SKP", [V(x)]) => Opcode::Skp { x: *x },
+        ("SKNP", [V(x)]) => Opcode::Sknp { x: *x },
+        ("PLANE", [n]) => Opcode::Plane(n.nibble()?),
+        ("AUDIO", []) => Opcode::Audio,
+        ("PITCH", [V(x)]) => Opcode::Pitch { x: *x },
+        _ => return Err(format!("unknown instruction {:?}", statement)),
+    };
+
+    Ok(opcode)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Operand<'a> {
+    V(u8),
+    I,
+    IndirectI,
+    Dt,
+    St,
+    K,
+    /// F and B are also hex digits, so keep the text
+    F(&'a str),
+    B(&'a str),
+    Long,
+    /// A number or a label
+    Value(&'a str),
+}
+
+impl<'a> Operand<'a> {
+    fn parse(operand: &'a str) -> Self {
+        match operand.to_ascii_uppercase().as_str() {
+            "I" => Operand::I,
+            "[I]" => Operand::IndirectI,
+            "DT" => Operand::Dt,
+            "ST" => Operand::St,
+            "K" => Operand::K,
+            "F" => Operand::F(operand),
+            "B" => Operand::B(operand),
+            "LONG" => Operand::Long,
+            // The disassembler prints V0 to V15, VA to VF are accepted too
+            register if register.starts_with('V') => match register_index(&register[1..]) {
+                Some(x) => Operand::V(x),
+                None => Operand::Value(operand),
+            },
+            _ => Operand::Value(operand),
+        }
+    }
+
+    fn value(self) -> Result<&'a str, String> {
+        match self {
+            Operand::Value(value) | Operand::F(value) | Operand::B(value) => Ok(value),
+            other => Err(format!("expected a number, got {:?}", other)),
+        }
+    }
+
+    fn address(self, labels: &BTreeMap<&str, u16>) -> Result<u16, String> {
+        address(self.value()?, labels, 0xFFF)
+    }
+
+    fn byte(self) -> Result<u8, String> {
+        let value = self.value()?;
+        match number(value)? {
+            byte @ 0..=0xFF => Ok(byte as u8),
+            _ => Err(format!("{} doesn't fit in a byte", value)),
+        }
+    }
+
+    fn nibble(self) -> Result<u8, String> {
+        let value = self.value()?;
+        match number(value)? {
+            nibble @ 0..=0xF => Ok(nibble as u8),
+            _ => Err(format!("{} doesn't fit in a nibble", value)),
+        }
+    }
+}
+
+fn register_index(digits: &str) -> Option<u8> {
+    let index = match digits.len() {
+        1 => u8::from_str_radix(digits, 16).ok()?,
+        2 if digits.bytes().all(|d| d.is_ascii_digit()) => digits.parse().ok()?,
+        _ => return None,
+    };
+    Some(index).filter(|&index| index < 16)
+}
+
+/// A label or a number no larger than `max`
+fn address(value: &str, labels: &BTreeMap<&str, u16>, max: u32) -> Result<u16, String> {
+    if let Some(&addr) = labels.get(value) {
+        if addr as u32 > max {
+            return Err(format!("label {} at {:x} out of range", value, addr));
+        }
+        return Ok(addr);
+    }
+    match number(value) {
+        Ok(addr) if addr <= max => Ok(addr as u16),
+        Ok(_) => Err(format!("address {} out of range", value)),
+        Err(_) => Err(format!("unknown label {}", value)),
+    }
+}
+
+/// Parse a hex number, with or without `0x`
+fn number(value: &str) -> Result<u32, String> {
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    u32::from_str_radix(digits, 16).map_err(|_| format!("invalid number {}", value))
+}
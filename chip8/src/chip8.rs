@@ -1,6 +1,13 @@
 #![allow(non_snake_case)]
 
+use crate::error::Chip8Error;
 use crate::io::*;
+use crate::opcode::{decode, Opcode, PATTERNS};
+use crate::quirks::{Mode, QuirkWarning, Quirks};
+#[cfg(feature = "debug")]
+use alloc::{format, string::ToString};
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
 
 #[rustfmt::skip]
 const FONTSET: [u8; 80] =
@@ -26,15 +33,185 @@ const FONTSET: [u8; 80] =
 pub const SCREEN_WIDTH: usize = 64;
 pub const SCREEN_HEIGHT: usize = 32;
 
-/// Address of PC start
-const GAME_ROM_OFFSET: usize = 0x200;
+/// SUPER-CHIP high resolution display size
+pub const HIRES_SCREEN_WIDTH: usize = 128;
+pub const HIRES_SCREEN_HEIGHT: usize = 64;
+
+/// Default address the ROM is loaded at and PC starts from
+pub const GAME_ROM_OFFSET: usize = 0x200;
+
+/// XO-CHIP pitch at startup, plays the audio pattern at 4000Hz
+const DEFAULT_PITCH: u8 = 64;
+
+/// Memory size of the original interpreters, and the only size without `alloc`
+pub const DEFAULT_MEMORY_SIZE: usize = 4096;
+
+/// Largest memory size, all that 16 bit addresses reach
+pub const MAX_MEMORY_SIZE: usize = 0x10000;
+
+/// Machine memory, on the heap when it can be resized
+#[cfg(feature = "alloc")]
+type Memory = alloc::boxed::Box<[u8]>;
+#[cfg(not(feature = "alloc"))]
+type Memory = [u8; DEFAULT_MEMORY_SIZE];
+
+/// Number of breakpoints that can be set at once
+pub const MAX_BREAKPOINTS: usize = 16;
+
+/// Load address of ROMs written for the ETI-660
+pub const ETI_660_ROM_OFFSET: usize = 0x600;
+
+/// Machine state for a debugger view, see `Chip8::debug_snapshot`
+#[cfg(feature = "debug")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugSnapshot {
+    pub pc: u16,
+    /// Instruction at PC, not yet executed
+    pub opcode: u16,
+    pub registers: [u8; 16],
+    pub i: u16,
+    /// Number of entries in use on the stack
+    pub sp: u8,
+    pub stack: [u16; 16],
+    pub delay: u8,
+    pub sound: u8,
+    /// Disassembly of `opcode`
+    pub mnemonic: String,
+}
+
+/// Rate the timers tick at and `Chip8::advance` is called at
+pub const TIMER_HZ: usize = 60;
+
+/// Instructions per frame for a CPU clock of `clock_hz`
+///
+/// Rounded to the nearest whole number, at least 1. A COSMAC VIP ran
+/// roughly 500 to 600 instructions per second, i.e. 8 to 10 per frame.
+pub const fn instructions_per_frame(clock_hz: usize) -> usize {
+    let ipf = (clock_hz + TIMER_HZ / 2) / TIMER_HZ;
+    if ipf == 0 {
+        1
+    } else {
+        ipf
+    }
+}
+
+/// What happened during one `Chip8::advance` frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    /// Instructions executed, less than requested if the frame ended early
+    pub executed: usize,
+    /// The buzzer should sound for this frame
+    pub sound: bool,
+    /// The display changed since the last frame or `take_display_dirty`
+    pub display_changed: bool,
+}
+
+/// Everything besides memory and the display that a snapshot restores
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CpuState {
+    I: u16,
+    PC: u16,
+    delay: u8,
+    sound: u8,
+    SP: u8,
+    stack: [u16; 16],
+    hires: bool,
+    planes: u8,
+    audio_pattern: [u8; 16],
+    audio_pattern_set: bool,
+    pitch: u8,
+    waiting_for_key: bool,
+    halted: bool,
+}
+
+/// Saved machine state, see `Chip8::snapshot`
+///
+/// Configuration like quirks and breakpoints, the random generator, the
+/// statistics counters and the keyboard aren't part of it. Keys are host
+/// input, restoring them would leave keys held that were released since.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    memory: Memory,
+    gfx: [u8; HIRES_SCREEN_WIDTH * HIRES_SCREEN_HEIGHT],
+    V: [u8; 16],
+    cpu: CpuState,
+}
+
+/// Changes between two states, see `Chip8::diff`
+///
+/// Memory, display and registers are stored as changed `(index, value)`
+/// pairs, the rest of the CPU state is small enough to always include.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateDelta {
+    memory: Vec<(u16, u8)>,
+    gfx: Vec<(u16, u8)>,
+    V: Vec<(u16, u8)>,
+    cpu: CpuState,
+}
+
+#[cfg(feature = "alloc")]
+impl StateDelta {
+    /// Number of changed memory bytes, display bytes and registers
+    pub fn changed(&self) -> usize {
+        self.memory.len() + self.gfx.len() + self.V.len()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Snapshot {
+    /// Changes that take a machine in the `other` state to this one
+    ///
+    /// Like `Chip8::diff`, but between two saved states. Diffing an older
+    /// snapshot against a newer one gives a delta that goes back in time.
+    pub fn diff(&self, other: &Snapshot) -> StateDelta {
+        StateDelta {
+            memory: changed_entries(&other.memory, &self.memory),
+            gfx: changed_entries(&other.gfx, &self.gfx),
+            V: changed_entries(&other.V, &self.V),
+            cpu: self.cpu,
+        }
+    }
+}
+
+/// Indices and new values of the entries that differ between the slices
+#[cfg(feature = "alloc")]
+fn changed_entries(old: &[u8], new: &[u8]) -> Vec<(u16, u8)> {
+    old.iter()
+        .zip(new)
+        .enumerate()
+        .filter(|(_, (old, new))| old != new)
+        .map(|(index, (_, &new))| (index as u16, new))
+        .collect()
+}
+
+/// Read a ROM out of `reader`
+///
+/// Stops one byte past `MAX_MEMORY_SIZE`, which no configuration can load, so
+/// an endless stream can't exhaust memory. Whether the ROM fits is checked
+/// when it is loaded.
+#[cfg(feature = "std")]
+pub fn read_rom<T: std::io::Read>(reader: &mut T) -> Result<Vec<u8>, Chip8Error> {
+    use std::io::Read;
+
+    let mut game = Vec::new();
+    reader
+        .take(MAX_MEMORY_SIZE as u64 + 1)
+        .read_to_end(&mut game)
+        .map_err(|e| Chip8Error::Io(e.kind()))?;
+    Ok(game)
+}
 
 /// CHIP-8 machine state
 pub struct Chip8<R>
 where
     R: Random,
 {
-    memory: [u8; 4096],
+    memory: Memory,
+    /// Glyphs 0 to F, copied to the start of memory
+    fontset: [u8; 80],
+    /// Where the ROM is loaded and PC starts
+    load_address: u16,
     /// 16 general purpose registers
     V: [u8; 16],
     /// Pointer register
@@ -53,236 +230,832 @@ where
     /// generic IO structs
     rand: R,
 
-    /// Graphics buffer
-    gfx: [u8; SCREEN_WIDTH * SCREEN_HEIGHT],
+    quirks: Quirks,
+    /// Detected quirk mismatches, one bit per `QuirkWarning`
+    quirk_warnings: u8,
+    /// The previous instruction was ADD I, Vx without `add_i_sets_vf`
+    after_add_i: bool,
+    /// Instructions executed since the machine was built or reset
+    instruction_count: u64,
+    /// Instructions executed per top nibble
+    opcode_histogram: [u64; 16],
+    /// Opcode families that fail with `NotImplementedYet`, one bit per family
+    disabled_opcodes: u64,
+
+    /// Graphics buffer, each pixel is a bitmask of the planes it is lit on.
+    /// Only the first `width * height` pixels of the active resolution are used
+    gfx: [u8; HIRES_SCREEN_WIDTH * HIRES_SCREEN_HEIGHT],
+    /// SUPER-CHIP high resolution mode is active
+    hires: bool,
+    /// Planes drawn on by DRW and cleared by CLS, only XO-CHIP changes it
+    planes: u8,
+    /// XO-CHIP audio, 128 one bit samples played while the sound timer runs
+    audio_pattern: [u8; 16],
+    /// F002 ran, until then frontends play their plain beep
+    audio_pattern_set: bool,
+    /// Sets the pattern playback rate, 64 is 4000 samples per second
+    pitch: u8,
+    /// Maximum times a return address may appear on the stack, disabled if None
+    max_recursion_depth: Option<usize>,
+    /// Set whenever the graphics buffer changes
+    display_dirty: bool,
     keyboard: [bool; 16],
+    /// LD Vx, K is waiting for a key to be pressed and released and will
+    /// run again
+    waiting_for_key: bool,
+    /// Keys pressed since LD Vx, K started waiting, one bit per key
+    key_wait_pressed: u16,
+    /// The last instruction was a JP to itself
+    halted: bool,
+    /// Addresses where `run_cycles` stops
+    breakpoints: [Option<u16>; MAX_BREAKPOINTS],
+    /// Length of the loaded ROM, starting at `load_address`
+    rom_len: usize,
+    /// FX33 and FX55 fail instead of writing into the ROM
+    write_protect_rom: bool,
+    /// Fetching from an odd PC fails instead of decoding garbage
+    strict_alignment: bool,
+    /// The last instruction was a DRW waiting for the vertical blank, see
+    /// `Quirks::display_wait`
+    vblank_wait: bool,
+    /// Receives a line per instruction, see `set_trace_writer`
+    #[cfg(feature = "std")]
+    trace_writer: Option<Box<dyn std::io::Write + Send>>,
 }
 
-impl<R> Chip8<R>
+/// Configures and builds a `Chip8`
+///
+/// The defaults match `Chip8::new`: COSMAC VIP quirks, the built-in fontset,
+/// the ROM loaded at 0x200 and the 64x32 display.
+pub struct Chip8Builder<'a, R>
+where
+    R: Random,
+{
+    game: &'a [u8],
+    rand: R,
+    quirks: Quirks,
+    fontset: [u8; 80],
+    load_address: u16,
+    high_res: bool,
+    #[cfg(feature = "alloc")]
+    memory_size: usize,
+}
+
+impl<'a, R> Chip8Builder<'a, R>
 where
     R: Random,
 {
-    /// Build a new Chip8 machine
-    pub fn new(game: &[u8], rand: R) -> Self {
-        let mut memory = [0; 4096];
-        memory[..FONTSET.len()].copy_from_slice(&FONTSET);
-        memory[GAME_ROM_OFFSET..(GAME_ROM_OFFSET + game.len())].copy_from_slice(game);
+    pub fn new(game: &'a [u8], rand: R) -> Self {
+        Chip8Builder {
+            game,
+            rand,
+            quirks: Quirks::default(),
+            fontset: FONTSET,
+            load_address: GAME_ROM_OFFSET as u16,
+            high_res: false,
+            #[cfg(feature = "alloc")]
+            memory_size: DEFAULT_MEMORY_SIZE,
+        }
+    }
+
+    /// Interpreter quirks
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Glyphs 0 to F, 5 bytes each, used by LD F, Vx
+    pub fn fontset(mut self, fontset: [u8; 80]) -> Self {
+        self.fontset = fontset;
+        self
+    }
+
+    /// Where the ROM is loaded and PC starts
+    pub fn load_address(mut self, load_address: u16) -> Self {
+        self.load_address = load_address;
+        self
+    }
+
+    /// Start in the SUPER-CHIP 128x64 mode
+    pub fn high_res(mut self, high_res: bool) -> Self {
+        self.high_res = high_res;
+        self
+    }
+
+    /// Bytes of memory, from `DEFAULT_MEMORY_SIZE` up to `MAX_MEMORY_SIZE`
+    ///
+    /// Only I reaches past 4K, through F000 NNNN and ADD I. The NNN operands
+    /// of JMP, CALL and LD I stay 12 bits like in XO-CHIP, so code runs from
+    /// the first 4K. I and everything read or written through it wrap at the
+    /// end of memory.
+    #[cfg(feature = "alloc")]
+    pub fn memory_size(mut self, memory_size: usize) -> Self {
+        self.memory_size = memory_size;
+        self
+    }
+
+    /// Fails with `RomTooLarge` if the game doesn't fit above the load address
+    /// and with `InvalidMemorySize` for a memory size out of range
+    pub fn build(self) -> Result<Chip8<R>, Chip8Error> {
+        #[cfg(feature = "alloc")]
+        let memory = {
+            if !(DEFAULT_MEMORY_SIZE..=MAX_MEMORY_SIZE).contains(&self.memory_size) {
+                return Err(Chip8Error::InvalidMemorySize {
+                    size: self.memory_size,
+                });
+            }
+            alloc::vec![0; self.memory_size].into_boxed_slice()
+        };
+        #[cfg(not(feature = "alloc"))]
+        let memory = [0; DEFAULT_MEMORY_SIZE];
 
-        Chip8 {
+        let mut chip8 = Chip8 {
             memory,
+            fontset: self.fontset,
+            load_address: self.load_address,
             V: [0; 16],
             I: 0,
-            PC: GAME_ROM_OFFSET as u16,
+            PC: self.load_address,
             delay: 0,
             sound: 0,
             SP: 0,
             stack: [0; 16],
-            rand,
-            gfx: [0; SCREEN_WIDTH * SCREEN_HEIGHT],
+            rand: self.rand,
+            quirks: self.quirks,
+            quirk_warnings: 0,
+            after_add_i: false,
+            instruction_count: 0,
+            opcode_histogram: [0; 16],
+            disabled_opcodes: 0,
+            gfx: [0; HIRES_SCREEN_WIDTH * HIRES_SCREEN_HEIGHT],
+            hires: false,
+            planes: 1,
+            audio_pattern: [0; 16],
+            audio_pattern_set: false,
+            pitch: DEFAULT_PITCH,
+            max_recursion_depth: None,
+            display_dirty: true,
             keyboard: [false; 16],
+            waiting_for_key: false,
+            key_wait_pressed: 0,
+            halted: false,
+            breakpoints: [None; MAX_BREAKPOINTS],
+            rom_len: 0,
+            write_protect_rom: false,
+            strict_alignment: false,
+            vblank_wait: false,
+            #[cfg(feature = "std")]
+            trace_writer: None,
+        };
+        chip8.load_rom(self.game)?;
+        chip8.hires = self.high_res;
+
+        Ok(chip8)
+    }
+}
+
+impl<R> Chip8<R>
+where
+    R: Random,
+{
+    /// Build a new Chip8 machine with the `Chip8Builder` defaults
+    ///
+    /// Fails with `RomTooLarge` if the game doesn't fit in memory above 0x200.
+    pub fn new(game: &[u8], rand: R) -> Result<Self, Chip8Error> {
+        Chip8Builder::new(game, rand).build()
+    }
+
+    /// Build a new Chip8 machine from a ROM read out of `reader`, with the
+    /// `Chip8Builder` defaults
+    ///
+    /// Fails with `RomTooLarge` like `new`. For a larger memory pass the
+    /// result of `read_rom` to the builder instead.
+    #[cfg(feature = "std")]
+    pub fn from_reader<T: std::io::Read>(reader: &mut T, rand: R) -> Result<Self, Chip8Error> {
+        Self::new(&read_rom(reader)?, rand)
+    }
+
+    /// Build a machine that loads the game and starts at `load_address`
+    /// instead of 0x200, e.g. `ETI_660_ROM_OFFSET` for ETI-660 ROMs
+    pub fn with_load_address(game: &[u8], rand: R, load_address: u16) -> Result<Self, Chip8Error> {
+        Chip8Builder::new(game, rand)
+            .load_address(load_address)
+            .build()
+    }
+
+    /// Replace the program with `game` and restart the machine
+    ///
+    /// Memory is cleared and the fontset restored, everything else is reset
+    /// like `reset`. The random source, quirks and opcode switches are kept.
+    pub fn load_rom(&mut self, game: &[u8]) -> Result<(), Chip8Error> {
+        let start = self.load_address as usize;
+        let max = self.memory.len().saturating_sub(start);
+        if game.len() > max {
+            return Err(Chip8Error::RomTooLarge {
+                size: game.len(),
+                max,
+            });
         }
+
+        self.reset();
+        self.memory.fill(0);
+        self.memory[..self.fontset.len()].copy_from_slice(&self.fontset);
+        self.memory[start..(start + game.len())].copy_from_slice(game);
+        self.rom_len = game.len();
+
+        Ok(())
     }
 
-    /// Execute a single instruction
-    pub fn execute_instruction(&mut self) {
+    /// Use the given quirks instead of the COSMAC VIP defaults
+    pub fn with_quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Switch quirks while running
+    ///
+    /// Only affects how the following instructions are interpreted, the
+    /// machine state is left alone. A ROM that already set up state for the
+    /// old quirks, like SUPER-CHIP high resolution, may need a `reset`.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Returns the active quirks
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    /// Returns the instruction at PC without executing it
+    ///
+    /// Nothing is modified, pass the result to `opcode::decode` to get the
+    /// opcode or its mnemonic.
+    pub fn peek_instruction(&self) -> u16 {
         // instructions are 16bit MSB
-        let instruction: u16 = ((self.memory[self.PC as usize] as u16) << 8)
-            + self.memory[(self.PC as usize) + 1] as u16;
-
-        // split into nibbles as the opcodes are based on nibbles
-        let opcode = (
-            (instruction & 0xF000) >> 12,
-            (instruction & 0x0F00) >> 8,
-            (instruction & 0x00F0) >> 4,
-            instruction & 0x000F,
-        );
+        self.read_word(self.PC)
+    }
+
+    /// Execute a single instruction
+    pub fn execute_instruction(&mut self) -> Result<(), Chip8Error> {
+        self.vblank_wait = false;
+        if self.strict_alignment && !self.PC.is_multiple_of(2) {
+            return Err(Chip8Error::MisalignedPc { pc: self.PC });
+        }
+        let instruction = self.peek_instruction();
+        #[cfg(feature = "std")]
+        self.write_trace(instruction)?;
+
+        let opcode = decode(instruction)
+            .filter(|opcode| opcode.available_in(self.quirks.mode))
+            .ok_or(Chip8Error::InvalidInstruction(instruction))?;
+        if self.disabled_opcodes & (1 << opcode.family()) != 0 {
+            return Err(Chip8Error::NotImplementedYet(opcode));
+        }
+
+        self.detect_quirk_mismatch(opcode);
+
+        let pc = self.PC;
+        self.PC = self.pc_plus(pc, 2);
+
+        // Leave PC on the failing instruction
+        self.execute(opcode).inspect_err(|_| self.PC = pc)?;
+        self.instruction_count += 1;
+        self.opcode_histogram[(instruction >> 12) as usize] += 1;
+
+        Ok(())
+    }
 
-        self.PC += 2;
+    /// Returns the number of instructions executed successfully
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    /// Returns the number of instructions executed per top nibble
+    ///
+    /// Entry 0xD counts DRW, entry 0x3 counts SE Vx, byte and so on.
+    pub fn opcode_histogram(&self) -> &[u64; 16] {
+        &self.opcode_histogram
+    }
+
+    /// Restart the program from the beginning
+    ///
+    /// Registers, timers, stack, display, keys and counters are cleared.
+    /// Memory is left untouched, so a ROM that modified itself stays modified.
+    /// Quirks and opcode switches are kept.
+    pub fn reset(&mut self) {
+        self.V = [0; 16];
+        self.I = 0;
+        self.PC = self.load_address;
+        self.delay = 0;
+        self.sound = 0;
+        self.SP = 0;
+        self.stack = [0; 16];
+        self.quirk_warnings = 0;
+        self.after_add_i = false;
+        self.instruction_count = 0;
+        self.opcode_histogram = [0; 16];
+        self.gfx = [0; HIRES_SCREEN_WIDTH * HIRES_SCREEN_HEIGHT];
+        self.hires = false;
+        self.planes = 1;
+        self.audio_pattern = [0; 16];
+        self.audio_pattern_set = false;
+        self.pitch = DEFAULT_PITCH;
+        self.display_dirty = true;
+        self.keyboard = [false; 16];
+        self.waiting_for_key = false;
+        self.key_wait_pressed = 0;
+        self.halted = false;
+    }
 
+    fn execute(&mut self, opcode: Opcode) -> Result<(), Chip8Error> {
         match opcode {
-            // CLS
-            (0, 0, 0xE, 0) => self.gfx.iter_mut().for_each(|m| *m = 0),
-            // RET
-            (0, 0, 0xE, 0xE) => self.PC = self.pop_stack() as u16,
-            // JMP nnn
-            (1, _, _, _) => self.PC = instruction & 0x0FFF,
-            // CALL nnn
-            (2, _, _, _) => {
-                self.push_stack(self.PC);
-                self.PC = instruction & 0x0FFF;
-            }
-            // SE Vx, byte
-            (3, x, _, _) => {
-                if self.V[x as usize] == (instruction & 0x00FF) as u8 {
-                    self.PC += 2;
+            Opcode::Cls => {
+                let planes = self.planes;
+                self.gfx.iter_mut().for_each(|m| *m &= !planes);
+                self.display_dirty = true;
+            }
+            Opcode::Ret => self.PC = self.pop_stack()?,
+            Opcode::Jmp(addr) => {
+                // PC already points past the jump
+                self.halted = addr == self.pc_plus(self.PC, -2);
+                self.PC = addr;
+            }
+            Opcode::Call(addr) => {
+                self.check_recursion(self.PC)?;
+                self.push_stack(self.PC)?;
+                self.PC = addr;
+            }
+            Opcode::SeVxByte { x, byte } => {
+                if self.V[x as usize] == byte {
+                    self.skip();
                 }
             }
-            // SNE Vx, byte
-            (4, x, _, _) => {
-                if self.V[x as usize] != (instruction & 0x00FF) as u8 {
-                    self.PC += 2;
+            Opcode::SneVxByte { x, byte } => {
+                if self.V[x as usize] != byte {
+                    self.skip();
                 }
             }
-            // SE Vx, Vy
-            (5, x, y, 0) => {
+            Opcode::SeVxVy { x, y } => {
                 if self.V[x as usize] == self.V[y as usize] {
-                    self.PC += 2;
+                    self.skip();
                 }
             }
-            // LD Vx, byte
-            (6, x, _, _) => self.V[x as usize] = (instruction & 0x00FF) as u8,
-            // ADD Vx, byte
-            (7, x, _, _) => {
-                self.V[x as usize] = self.V[x as usize].wrapping_add((instruction & 0x00FF) as u8)
-            }
-            // LD Vx, Vy
-            (8, x, y, 0) => self.V[x as usize] = self.V[y as usize],
-            // OR Vx, Vy
-            (8, x, y, 1) => self.V[x as usize] |= self.V[y as usize],
-            // AND Vx, Vy
-            (8, x, y, 2) => self.V[x as usize] &= self.V[y as usize],
-            // XOR Vx, Vy
-            (8, x, y, 3) => self.V[x as usize] ^= self.V[y as usize],
-            // ADD Vx, Vy
-            (8, x, y, 4) => {
+            Opcode::LdVxByte { x, byte } => self.V[x as usize] = byte,
+            Opcode::AddVxByte { x, byte } => {
+                self.V[x as usize] = self.V[x as usize].wrapping_add(byte)
+            }
+            Opcode::LdVxVy { x, y } => self.V[x as usize] = self.V[y as usize],
+            Opcode::Or { x, y } => self.V[x as usize] |= self.V[y as usize],
+            Opcode::And { x, y } => self.V[x as usize] &= self.V[y as usize],
+            Opcode::Xor { x, y } => self.V[x as usize] ^= self.V[y as usize],
+            Opcode::AddVxVy { x, y } => {
                 let (res, carry) = self.V[x as usize].overflowing_add(self.V[y as usize]);
                 self.V[x as usize] = res;
                 self.V[0xF] = carry as u8;
             }
-            // SUB Vx, Vy
-            (8, x, y, 5) => {
-                let (res, carry) = self.V[x as usize].overflowing_sub(self.V[y as usize]);
+            Opcode::Sub { x, y } => {
+                // VF is NOT borrow, 1 when Vx >= Vy
+                let (res, borrow) = self.V[x as usize].overflowing_sub(self.V[y as usize]);
                 self.V[x as usize] = res;
-                self.V[0xF] = carry as u8;
+                self.V[0xF] = !borrow as u8;
             }
-            // SHR Vx, Vy
-            (8, x, y, 6) => {
+            Opcode::Shr { x, y } => {
                 self.V[0xF] = if self.V[y as usize] & 1 != 0 { 1 } else { 0 };
                 self.V[x as usize] = self.V[y as usize] >> 1;
             }
-            // SUBN Vx, Vy
-            (8, x, y, 7) => {
-                let (res, carry) = self.V[y as usize].overflowing_sub(self.V[x as usize]);
+            Opcode::Subn { x, y } => {
+                let (res, borrow) = self.V[y as usize].overflowing_sub(self.V[x as usize]);
                 self.V[x as usize] = res;
-                self.V[0xF] = carry as u8;
+                self.V[0xF] = !borrow as u8;
             }
-            // SHL Vx, Vy
-            (8, x, y, 0xE) => {
+            Opcode::Shl { x, y } => {
                 self.V[0xF] = if self.V[y as usize] & 0x80 != 0 { 1 } else { 0 };
                 self.V[x as usize] = self.V[y as usize] << 1;
             }
-            // SNE Vx, Vy
-            (9, x, y, 0) => {
+            Opcode::SneVxVy { x, y } => {
                 if self.V[x as usize] != self.V[y as usize] {
-                    self.PC += 2;
+                    self.skip();
                 }
             }
-            // LD I, addr
-            (0xA, _, _, _) => self.I = instruction & 0x0FFF,
-            // JP V0, addr
-            (0xB, _, _, _) => self.PC = (instruction & 0x0FFF) + self.V[0] as u16,
-            // RND Vx, byte
-            (0xC, x, _, _) => {
-                self.V[x as usize] = (instruction & 0x00FF) as u8 & self.rand.randint()
-            }
-            // DRW Vx, Vy, nibble
-            (0xD, x, y, n) => {
-                // Clear collision
-                self.V[0xF] = 0;
-                let x = self.V[x as usize] as u16;
-                let y = self.V[y as usize] as u16;
-
-                for yl in 0..n {
-                    let pixels = self.memory[(self.I + yl) as usize];
-                    for xl in 0..8 {
-                        if pixels & (0x80 >> xl) != 0 {
-                            let index = (x + xl + ((y + yl) * 64)) as usize;
-                            // Collision detection
-                            if self.gfx[index % 2048] == 1 {
-                                self.V[0xF] = 1;
+            Opcode::LdI(addr) => self.I = addr,
+            Opcode::JpV0(addr) => {
+                let offset = if self.quirks.jump_with_vx {
+                    self.V[(addr >> 8) as usize]
+                } else {
+                    self.V[0]
+                };
+                self.PC = self.pc_plus(addr, offset as isize);
+            }
+            Opcode::Rnd { x, byte } => self.V[x as usize] = byte & self.rand.randint(),
+            Opcode::Drw { x, y, n } => {
+                self.display_dirty = true;
+                self.vblank_wait = self.quirks.display_wait;
+                let (width, height) = self.display_dimensions();
+                // The starting position always wraps. Read it before VF is
+                // overwritten, VF may be one of the coordinates.
+                let x = self.V[x as usize] as usize % width;
+                let y = self.V[y as usize] as usize % height;
+                // Set when any lit pixel is turned off
+                let mut collision = false;
+
+                // DXY0 draws a 16x16 sprite, two bytes per row
+                let (rows, columns) = match n {
+                    0 if self.hires || self.quirks.lores_large_sprites => (16, 16),
+                    _ => (n as usize, 8),
+                };
+                let row_bytes = columns / 8;
+
+                // Each selected plane is drawn with its own sprite data,
+                // stored one after the other
+                let mut offset = 0;
+                for plane in [1, 2] {
+                    if self.planes & plane == 0 {
+                        continue;
+                    }
+
+                    for yl in 0..rows {
+                        let mut py = y + yl;
+                        if py >= height {
+                            if !self.quirks.wrap_sprites {
+                                break;
+                            }
+                            py %= height;
+                        }
+
+                        for xl in 0..columns {
+                            let mut px = x + xl;
+                            if px >= width {
+                                if !self.quirks.wrap_sprites {
+                                    break;
+                                }
+                                px %= width;
+                            }
+
+                            let pixels =
+                                self.memory[self.address(offset + yl * row_bytes + xl / 8)];
+                            if pixels & (0x80 >> (xl % 8)) != 0 {
+                                let index = py * width + px;
+                                collision |= self.gfx[index] & plane != 0;
+                                self.gfx[index] ^= plane
                             }
-                            self.gfx[index % 2048] ^= 1
                         }
                     }
+                    offset += rows * row_bytes;
                 }
+                self.V[0xF] = collision as u8;
             }
-            // SKP Vx
-            (0xE, x, 9, 0xE) => {
+            Opcode::Skp { x } => {
                 if self.key_pressed(self.V[x as usize]) {
-                    self.PC += 2;
+                    self.skip();
                 }
             }
-            // SKNP Vx
-            (0xE, x, 0xA, 1) => {
+            Opcode::Sknp { x } => {
                 if !self.key_pressed(self.V[x as usize]) {
-                    self.PC += 2;
+                    self.skip();
                 }
             }
-            // LD Vx, DT
-            (0xF, x, 0, 7) => {
+            Opcode::LdVxDt { x } => {
                 self.V[x as usize] = self.delay;
             }
-            // LD Vx, K
-            (0xF, x, 0, 0xA) => {
-                let mut pressed = false;
-
+            Opcode::LdVxK { x } => {
+                // Like the COSMAC VIP, wait for a key to be pressed and then
+                // released. With several keys down the lowest one counts.
                 for i in 0..16 {
                     if self.key_pressed(i) {
-                        self.V[x as usize] = i;
-                        pressed = true;
+                        self.key_wait_pressed |= 1 << i;
                     }
                 }
 
-                if !pressed {
-                    self.PC -= 2;
+                let lowest = self.key_wait_pressed.trailing_zeros() as u8;
+                let released = self.key_wait_pressed != 0 && !self.key_pressed(lowest);
+                if released {
+                    self.V[x as usize] = lowest;
+                    self.key_wait_pressed = 0;
+                } else {
+                    self.PC = self.pc_plus(self.PC, -2);
                 }
+                self.waiting_for_key = !released;
             }
-            // LD DT, Vx
-            (0xF, x, 1, 5) => self.delay = self.V[x as usize],
-            // LD ST, Vx
-            (0xF, x, 1, 8) => self.sound = self.V[x as usize],
-            // ADD I, Vx
-            (0xF, x, 1, 0xE) => {
-                let (res, carry) = self.I.overflowing_add(self.V[x as usize] as u16);
-                self.I = res;
-                self.V[0xF] = carry as u8;
+            Opcode::LdDtVx { x } => self.delay = self.V[x as usize],
+            Opcode::LdStVx { x } => self.sound = self.V[x as usize],
+            Opcode::AddIVx { x } => {
+                // I is kept as 16 bits and only wraps at 0xFFFF, memory
+                // accesses through it wrap at the end of memory instead
+                self.I = self.I.wrapping_add(self.V[x as usize] as u16);
+                if self.quirks.add_i_sets_vf {
+                    // The carry is out of the 12 bit address space
+                    self.V[0xF] = (self.I > 0x0FFF) as u8;
+                }
+            }
+            Opcode::LdFVx { x } => {
+                // Only digits 0 to F have glyphs, larger values use the low nibble
+                self.I = (self.V[x as usize] & 0xF) as u16 * 5;
             }
-            // LD F, Vx
-            (0xF, x, 2, 9) => self.I = (self.V[x as usize] * 5) as u16,
-            // LD B, Vx
-            (0xF, x, 3, 3) => {
+            Opcode::LdBVx { x } => {
+                self.check_rom_write(3)?;
                 let vx = self.V[x as usize];
-                self.memory[self.I as usize] = vx / 100;
-                self.memory[self.I as usize + 1] = (vx / 10) % 10;
-                self.memory[self.I as usize + 2] = vx % 10;
+                self.memory[self.address(0)] = vx / 100;
+                self.memory[self.address(1)] = (vx / 10) % 10;
+                self.memory[self.address(2)] = vx % 10;
             }
-            // LD [I], Vx
-            (0xF, x, 5, 5) => {
+            Opcode::LdIVx { x } => {
+                self.check_rom_write(x as usize + 1)?;
                 for i in 0..(x as usize + 1) {
-                    self.memory[self.I as usize + i] = self.V[i];
+                    self.memory[self.address(i)] = self.V[i];
                 }
-                self.I += x + 1;
+                self.I = self.I.wrapping_add(x as u16 + 1);
             }
-            // LD Vx, [I]
-            (0xF, x, 6, 5) => {
+            Opcode::LdVxI { x } => {
                 for i in 0..(x as usize + 1) {
-                    self.V[i] = self.memory[self.I as usize + i];
+                    self.V[i] = self.memory[self.address(i)];
+                }
+                self.I = self.I.wrapping_add(x as u16 + 1);
+            }
+            Opcode::LdILong => {
+                self.I = self.read_word(self.PC);
+                self.PC = self.pc_plus(self.PC, 2);
+            }
+            Opcode::Plane(n) => self.planes = n & 0b11,
+            Opcode::Audio => {
+                for i in 0..self.audio_pattern.len() {
+                    self.audio_pattern[i] = self.memory[self.address(i)];
                 }
-                self.I += x + 1;
+                self.audio_pattern_set = true;
             }
+            Opcode::Pitch { x } => self.pitch = self.V[x as usize],
+            Opcode::LoRes => self.set_hires(false),
+            Opcode::HiRes => self.set_hires(true),
+        }
+
+        Ok(())
+    }
+
+    /// Switch resolution, clearing the display
+    fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.gfx.iter_mut().for_each(|m| *m = 0);
+        self.display_dirty = true;
+    }
 
-            (_, _, _, _) => panic!("Invalid instruction {:?}!", opcode),
+    /// Skip the next instruction, XO-CHIP skips both words of F000 NNNN
+    fn skip(&mut self) {
+        if self.quirks.mode == Mode::XoChip && self.read_word(self.PC) == 0xF000 {
+            self.PC = self.pc_plus(self.PC, 2);
         }
+        self.PC = self.pc_plus(self.PC, 2);
     }
 
-    fn push_stack(&mut self, val: u16) {
+    /// `pc + offset` wrapped around the end of memory
+    fn pc_plus(&self, pc: u16, offset: isize) -> u16 {
+        (pc as isize + offset).rem_euclid(self.memory.len() as isize) as u16
+    }
+
+    fn read_word(&self, addr: u16) -> u16 {
+        (self.read_memory(addr) as u16) << 8 | self.read_memory(addr.wrapping_add(1)) as u16
+    }
+
+    /// Memory index of `I + offset`, wrapping around the end of memory
+    fn address(&self, offset: usize) -> usize {
+        (self.I as usize + offset) % self.memory.len()
+    }
+
+    /// Returns the quirk mismatches detected so far
+    ///
+    /// These are heuristics, a warning means the ROM behaves as if it expects
+    /// a quirk that is currently disabled.
+    pub fn quirk_warnings(&self) -> impl Iterator<Item = QuirkWarning> + '_ {
+        QuirkWarning::ALL
+            .iter()
+            .copied()
+            .filter(move |&w| self.quirk_warnings & (1 << w as u8) != 0)
+    }
+
+    fn detect_quirk_mismatch(&mut self, opcode: Opcode) {
+        if self.after_add_i && opcode.reads_register(0xF) {
+            self.quirk_warnings |= 1 << QuirkWarning::AddISetsVf as u8;
+        }
+        self.after_add_i = matches!(opcode, Opcode::AddIVx { .. }) && !self.quirks.add_i_sets_vf;
+    }
+
+    /// Make an opcode family fail with `NotImplementedYet` instead of executing
+    ///
+    /// The family is given by its pattern, e.g. `8xy4`. Returns false if the
+    /// pattern is unknown.
+    pub fn disable_opcode(&mut self, pattern: &str) -> bool {
+        match Self::opcode_family(pattern) {
+            Some(family) => {
+                self.disabled_opcodes |= 1 << family;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-enable an opcode family disabled with `disable_opcode`
+    ///
+    /// Returns false if the pattern is unknown.
+    pub fn enable_opcode(&mut self, pattern: &str) -> bool {
+        match Self::opcode_family(pattern) {
+            Some(family) => {
+                self.disabled_opcodes &= !(1 << family);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn opcode_family(pattern: &str) -> Option<usize> {
+        PATTERNS
+            .iter()
+            .position(|p| p.eq_ignore_ascii_case(pattern))
+    }
+
+    /// Execute up to `n` instructions and return how many were executed
+    ///
+    /// Stops early after an LD Vx, K that found no key pressed, after a DRW
+    /// with the `display_wait` quirk, once the machine is halted, and before
+    /// an instruction at a breakpoint. The first instruction always runs, so
+    /// calling again resumes from a breakpoint. On error the count is lost,
+    /// `instruction_count` still has the total.
+    pub fn run_cycles(&mut self, n: usize) -> Result<usize, Chip8Error> {
+        for executed in 0..n {
+            if executed > 0 && self.breakpoints.contains(&Some(self.PC)) {
+                return Ok(executed);
+            }
+
+            self.execute_instruction()?;
+            if self.waiting_for_key || self.vblank_wait || self.halted {
+                return Ok(executed + 1);
+            }
+        }
+
+        Ok(n)
+    }
+
+    /// Returns true once the program jumped to its own address
+    ///
+    /// `1NNN` with NNN pointing at itself is the usual way to end a program,
+    /// it would spin forever so frontends can stop stepping instead.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Returns true while LD Vx, K is blocked waiting for a key press and
+    /// release
+    pub fn waiting_for_key(&self) -> bool {
+        self.waiting_for_key
+    }
+
+    /// Stop `run_cycles` before the instruction at `addr`
+    ///
+    /// Returns false if `MAX_BREAKPOINTS` are already set.
+    pub fn add_breakpoint(&mut self, addr: u16) -> bool {
+        if self.breakpoints.contains(&Some(addr)) {
+            return true;
+        }
+        match self.breakpoints.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(addr);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the breakpoint at `addr`, returns false if there was none
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool {
+        match self
+            .breakpoints
+            .iter_mut()
+            .find(|slot| **slot == Some(addr))
+        {
+            Some(slot) => {
+                *slot = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Limit how many times the same return address can be on the stack
+    ///
+    /// A CALL that would exceed the limit fails with `RecursionLimitExceeded`.
+    /// Disabled by default.
+    pub fn set_max_recursion_depth(&mut self, depth: Option<usize>) {
+        self.max_recursion_depth = depth;
+    }
+
+    /// Make FX33 and FX55 fail with `RomWrite` instead of writing into the
+    /// loaded ROM
+    ///
+    /// Catches a runaway I overwriting code. Off by default since some
+    /// programs modify themselves on purpose.
+    pub fn set_write_protect_rom(&mut self, on: bool) {
+        self.write_protect_rom = on;
+    }
+
+    /// Make fetching an instruction from an odd address fail with
+    /// `MisalignedPc`
+    ///
+    /// Catches a miscomputed jump where it happens instead of a few garbage
+    /// instructions later. Off by default since a few ROMs run code at odd
+    /// addresses on purpose.
+    pub fn set_strict_alignment(&mut self, on: bool) {
+        self.strict_alignment = on;
+    }
+
+    /// Write a trace line to `writer` before every instruction, including
+    /// one that then fails
+    ///
+    /// Each line is `PC OPCODE I SP V0 .. VF` in lowercase hex, separated by
+    /// single spaces, with PC, OPCODE and I four digits wide and the rest
+    /// two:
+    ///
+    /// ```text
+    /// 0200 6005 0000 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00
+    /// ```
+    ///
+    /// The state is the one before the instruction runs. This format is
+    /// stable, traces from the same ROM and seed on another emulator can be
+    /// diffed against it to find the first divergence. A failed write stops
+    /// execution with `Chip8Error::Io`.
+    #[cfg(feature = "std")]
+    pub fn set_trace_writer<W: std::io::Write + Send + 'static>(&mut self, writer: W) {
+        self.trace_writer = Some(Box::new(writer));
+    }
+
+    /// Stop tracing, the writer is flushed and dropped
+    #[cfg(feature = "std")]
+    pub fn clear_trace_writer(&mut self) -> Result<(), Chip8Error> {
+        match self.trace_writer.take() {
+            Some(mut writer) => writer.flush().map_err(|e| Chip8Error::Io(e.kind())),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn write_trace(&mut self, instruction: u16) -> Result<(), Chip8Error> {
+        let writer = match &mut self.trace_writer {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+
+        let mut line = format!(
+            "{:04x} {:04x} {:04x} {:02x}",
+            self.PC, instruction, self.I, self.SP
+        );
+        for v in &self.V {
+            line.push_str(&format!(" {:02x}", v));
+        }
+        writeln!(writer, "{}", line).map_err(|e| Chip8Error::Io(e.kind()))
+    }
+
+    /// Fail if a write of `len` bytes at I would touch the protected ROM
+    fn check_rom_write(&self, len: usize) -> Result<(), Chip8Error> {
+        if !self.write_protect_rom {
+            return Ok(());
+        }
+        let rom = self.load_address as usize..self.load_address as usize + self.rom_len;
+        match (0..len)
+            .map(|i| self.address(i))
+            .find(|addr| rom.contains(addr))
+        {
+            Some(addr) => Err(Chip8Error::RomWrite { addr: addr as u16 }),
+            None => Ok(()),
+        }
+    }
+
+    fn check_recursion(&self, return_addr: u16) -> Result<(), Chip8Error> {
+        if let Some(max) = self.max_recursion_depth {
+            let depth = self.stack[..self.SP as usize]
+                .iter()
+                .filter(|&&addr| addr == return_addr)
+                .count();
+
+            if depth >= max {
+                return Err(Chip8Error::RecursionLimitExceeded {
+                    addr: self.pc_plus(return_addr, -2),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn push_stack(&mut self, val: u16) -> Result<(), Chip8Error> {
+        if self.SP as usize >= self.stack.len() {
+            return Err(Chip8Error::StackOverflow);
+        }
         self.stack[self.SP as usize] = val;
         self.SP += 1;
+        Ok(())
     }
 
-    fn pop_stack(&mut self) -> u16 {
+    fn pop_stack(&mut self) -> Result<u16, Chip8Error> {
+        if self.SP == 0 {
+            return Err(Chip8Error::StackUnderflow);
+        }
         self.SP -= 1;
-        self.stack[self.SP as usize]
+        Ok(self.stack[self.SP as usize])
     }
 
     fn key_pressed(&self, key: u8) -> bool {
@@ -290,15 +1063,351 @@ where
     }
 
     /// Set the pressed state of a key
-    pub fn set_key(&mut self, key: u8, state: bool) {
-        if key < 16 {
-            self.keyboard[key as usize] = state;
+    ///
+    /// Setting the current state again changes nothing. Returns false and
+    /// changes nothing if `key` is not a keypad key (0 to F).
+    pub fn set_key(&mut self, key: u8, state: bool) -> bool {
+        match self.keyboard.get_mut(key as usize) {
+            Some(pressed) => {
+                *pressed = state;
+                // A press and release between two runs of LD Vx, K still counts
+                if state && self.waiting_for_key {
+                    self.key_wait_pressed |= 1 << key;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the pressed state of every key, indexed by key
+    pub fn keyboard_state(&self) -> &[bool; 16] {
+        &self.keyboard
+    }
+
+    /// Returns the number of bytes of memory
+    pub fn memory_size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Read a byte of memory, out of range addresses read as 0
+    pub fn read_memory(&self, addr: u16) -> u8 {
+        self.memory.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    /// Write a byte of memory, out of range addresses are ignored
+    pub fn write_memory(&mut self, addr: u16, val: u8) {
+        if let Some(byte) = self.memory.get_mut(addr as usize) {
+            *byte = val;
         }
     }
 
-    /// Returns the state of a pixel
+    /// Format `len` bytes of memory from `start` as a hex dump
+    ///
+    /// Each row holds the address, 16 bytes in hex and their printable ASCII
+    /// characters. The range is clamped to the end of memory.
+    #[cfg(feature = "alloc")]
+    pub fn dump_memory(&self, start: u16, len: usize) -> String {
+        use core::fmt::Write;
+
+        let start = start as usize;
+        let end = start.saturating_add(len).min(self.memory.len());
+
+        let mut dump = String::new();
+        for row in (start..end).step_by(16) {
+            let bytes: Vec<u8> = (row..end.min(row + 16))
+                .map(|addr| self.read_memory(addr as u16))
+                .collect();
+
+            let _ = write!(dump, "{:04x} ", row);
+            for byte in &bytes {
+                let _ = write!(dump, " {:02x}", byte);
+            }
+            // Keep the ASCII column aligned on a short last row
+            for _ in bytes.len()..16 {
+                dump.push_str("   ");
+            }
+            dump.push_str("  |");
+            for &byte in &bytes {
+                dump.push(if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                });
+            }
+            dump.push_str("|\n");
+        }
+
+        dump
+    }
+
+    /// Write a disassembly of memory from `start` up to `end` to `out`
+    ///
+    /// One `addr: word  mnemonic` line per two bytes, words that don't decode
+    /// in the current mode are written as `DB 0xnnnn`. A lone byte left at
+    /// the end of the range is written as `DB 0xnn`. The range is clamped to
+    /// the end of memory.
+    #[cfg(feature = "std")]
+    pub fn disassemble_to<W: std::io::Write>(
+        &self,
+        out: &mut W,
+        start: u16,
+        end: u16,
+    ) -> std::io::Result<()> {
+        let end = (end as usize).min(self.memory.len());
+
+        for addr in (start as usize..end).step_by(2) {
+            if addr + 1 == end {
+                writeln!(
+                    out,
+                    "{:04x}: {:02x}    DB 0x{:02x}",
+                    addr, self.memory[addr], self.memory[addr]
+                )?;
+                break;
+            }
+
+            let word = self.read_word(addr as u16);
+            match decode(word).filter(|opcode| opcode.available_in(self.quirks.mode)) {
+                Some(opcode) => writeln!(out, "{:04x}: {:04x}  {}", addr, word, opcode)?,
+                None => writeln!(out, "{:04x}: {:04x}  DB 0x{:04x}", addr, word, word)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the general purpose registers
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.V
+    }
+
+    /// Set a general purpose register, out of range indices are ignored
+    pub fn set_register(&mut self, index: usize, val: u8) {
+        if index < 16 {
+            self.V[index] = val;
+        }
+    }
+
+    /// Returns the program counter
+    pub fn program_counter(&self) -> u16 {
+        self.PC
+    }
+
+    /// Returns the pointer register
+    pub fn index_register(&self) -> u16 {
+        self.I
+    }
+
+    /// Returns the state of a pixel, lit on any plane
     pub fn get_pixel(&self, x: usize, y: usize) -> bool {
-        self.gfx[y * SCREEN_WIDTH + x] != 0
+        self.get_pixel_index(x, y) != 0
+    }
+
+    /// Returns the color index of a pixel, combining the planes
+    ///
+    /// 0 is the background, 1 lit on the first plane only, 2 on the second
+    /// XO-CHIP plane only and 3 on both. Without XO-CHIP planes it is only
+    /// ever 0 or 1, same as `get_pixel`.
+    pub fn get_pixel_index(&self, x: usize, y: usize) -> u8 {
+        self.gfx[y * self.display_dimensions().0 + x]
+    }
+
+    /// Returns the raw graphics buffer, one byte per pixel in row-major order
+    ///
+    /// Each byte is a color index: bit 0 is set for pixels lit on the first
+    /// plane and bit 1 for the second XO-CHIP plane. Plain CHIP-8 only uses 0
+    /// and 1.
+    pub fn get_display(&self) -> &[u8] {
+        let (width, height) = self.display_dimensions();
+        &self.gfx[..width * height]
+    }
+
+    /// Returns the width and height of the display in pixels
+    ///
+    /// This is 128x64 while SUPER-CHIP high resolution mode is active.
+    pub fn display_dimensions(&self) -> (usize, usize) {
+        if self.hires {
+            (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT)
+        } else {
+            (SCREEN_WIDTH, SCREEN_HEIGHT)
+        }
+    }
+
+    /// Iterate over every pixel as `(x, y, lit)` in row-major order
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize, bool)> + '_ {
+        let (width, _) = self.display_dimensions();
+        self.get_display()
+            .iter()
+            .enumerate()
+            .map(move |(i, &pixel)| (i % width, i / width, pixel != 0))
+    }
+
+    /// Render the display as an SVG image, each pixel is a `scale` sized square
+    #[cfg(feature = "std")]
+    pub fn to_svg(&self, scale: usize) -> String {
+        use std::fmt::Write;
+
+        let (width, height) = self.display_dimensions();
+        let (width, height) = (width * scale, height * scale);
+
+        let mut svg = String::new();
+        let _ = write!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+            width, height
+        );
+        svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"black\"/>");
+        for (x, y, _) in self.pixels().filter(|&(_, _, lit)| lit) {
+            let _ = write!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"white\"/>",
+                x * scale,
+                y * scale,
+                scale
+            );
+        }
+        svg.push_str("</svg>");
+
+        svg
+    }
+
+    /// Returns true if the display changed since the last call and clears the flag
+    pub fn take_display_dirty(&mut self) -> bool {
+        let dirty = self.display_dirty;
+        self.display_dirty = false;
+        dirty
+    }
+
+    /// Pack the display into `out`, one bit per pixel, MSB first
+    ///
+    /// `out` must hold at least `width * height / 8` bytes
+    pub fn render_packed_into(&self, out: &mut [u8]) -> Result<(), Chip8Error> {
+        let required = self.get_display().len() / 8;
+        if out.len() < required {
+            return Err(Chip8Error::BufferTooSmall {
+                required,
+                actual: out.len(),
+            });
+        }
+
+        for (byte, pixels) in out.iter_mut().zip(self.get_display().chunks_exact(8)) {
+            *byte = pixels
+                .iter()
+                .fold(0, |acc, &pixel| (acc << 1) | (pixel != 0) as u8);
+        }
+
+        Ok(())
+    }
+
+    /// Packed counterpart of `get_display`, one bit per pixel, MSB first
+    /// within each row
+    ///
+    /// `out` needs `SCREEN_WIDTH * SCREEN_HEIGHT / 8` bytes, or 1024 in
+    /// high resolution, smaller buffers fail with `BufferTooSmall`. Same as
+    /// `render_packed_into`.
+    pub fn get_display_packed(&self, out: &mut [u8]) -> Result<(), Chip8Error> {
+        self.render_packed_into(out)
+    }
+
+    /// Unpack a display packed by `render_packed_into`
+    ///
+    /// `packed` must be exactly `width * height / 8` bytes
+    pub fn load_packed_framebuffer(&mut self, packed: &[u8]) -> Result<(), Chip8Error> {
+        let expected = self.get_display().len() / 8;
+        if packed.len() != expected {
+            return Err(Chip8Error::BufferLengthMismatch {
+                expected,
+                actual: packed.len(),
+            });
+        }
+
+        for (&byte, pixels) in packed
+            .iter()
+            .zip(self.gfx[..expected * 8].chunks_exact_mut(8))
+        {
+            for (bit, pixel) in pixels.iter_mut().enumerate() {
+                *pixel = (byte >> (7 - bit)) & 1;
+            }
+        }
+        self.display_dirty = true;
+
+        Ok(())
+    }
+
+    /// Run one 60Hz frame: execute `cycles` instructions, then tick the timers
+    ///
+    /// Returns true while the buzzer should sound. The effective clock speed
+    /// is `cycles * 60` instructions per second.
+    ///
+    /// The frame ends early when `run_cycles` stops at a key wait or a
+    /// breakpoint.
+    pub fn step_frame(&mut self, cycles: usize) -> Result<bool, Chip8Error> {
+        self.run_cycles(cycles)?;
+
+        let sound = self.sound_active();
+        self.tick_timers();
+        Ok(sound)
+    }
+
+    /// Run one 60Hz frame of `ipf` instructions and report what changed
+    ///
+    /// The effective clock speed is `ipf * TIMER_HZ` instructions per
+    /// second, `instructions_per_frame` converts a clock speed to `ipf`. The
+    /// timers tick once per call no matter how many instructions ran.
+    ///
+    /// A halted machine executes nothing. While LD Vx, K is blocked only the
+    /// key check runs, same as with `run_cycles`. Either way the timers keep
+    /// ticking. Consumes the display dirty flag.
+    pub fn advance(&mut self, ipf: usize) -> Result<Frame, Chip8Error> {
+        let executed = if self.halted {
+            0
+        } else {
+            self.run_cycles(ipf)?
+        };
+
+        let sound = self.sound_active();
+        self.tick_timers();
+
+        Ok(Frame {
+            executed,
+            sound,
+            display_changed: self.take_display_dirty(),
+        })
+    }
+
+    /// Execute a single instruction without ticking the timers
+    ///
+    /// Meant for single stepping a paused machine, timers only advance with
+    /// `step_frame`.
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        self.execute_instruction()
+    }
+
+    /// Like `step`, but a CALL runs until its subroutine returns
+    ///
+    /// Stops once the stack is back at the depth before the CALL, normally on
+    /// the instruction after it. Also stops after `max_cycles` instructions,
+    /// at a breakpoint, on a key wait or once the machine is halted, like
+    /// `run_cycles`.
+    pub fn step_over(&mut self, max_cycles: usize) -> Result<(), Chip8Error> {
+        if !matches!(decode(self.peek_instruction()), Some(Opcode::Call(_))) {
+            return self.step();
+        }
+
+        let depth = self.SP;
+        for executed in 0..max_cycles {
+            if executed > 0
+                && (self.SP <= depth
+                    || self.breakpoints.contains(&Some(self.PC))
+                    || self.waiting_for_key
+                    || self.halted)
+            {
+                break;
+            }
+            self.execute_instruction()?;
+        }
+
+        Ok(())
     }
 
     /// Decrement the delay counter
@@ -308,9 +1417,57 @@ where
         }
     }
 
+    /// Decrement both timers, call at 60Hz
+    pub fn tick_timers(&mut self) {
+        self.delay = self.delay.saturating_sub(1);
+        self.sound = self.sound.saturating_sub(1);
+    }
+
+    /// Returns true while the buzzer should sound
+    pub fn sound_active(&self) -> bool {
+        self.sound > 0
+    }
+
+    /// XO-CHIP audio pattern, 128 samples of one bit, MSB first
+    ///
+    /// Played in a loop at `pattern_rate` while the sound timer runs. All
+    /// zero until F002 loads one, see `has_audio_pattern`.
+    pub fn audio_buffer(&self) -> &[u8; 16] {
+        &self.audio_pattern
+    }
+
+    /// Returns true once F002 loaded an audio pattern
+    ///
+    /// Until then frontends should play their plain beep.
+    pub fn has_audio_pattern(&self) -> bool {
+        self.audio_pattern_set
+    }
+
+    /// Returns the XO-CHIP pitch register set by FX3A
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    /// Audio pattern samples per second, `4000 * 2^((pitch - 64) / 48)`
+    #[cfg(feature = "std")]
+    pub fn pattern_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+
+    /// Returns the sound timer
+    pub fn sound_timer(&self) -> u8 {
+        self.sound
+    }
+
+    /// Returns the delay timer
+    pub fn delay_timer(&self) -> u8 {
+        self.delay
+    }
+
     /// Decrement sound counter
     ///
-    /// Returns true if it is already 0
+    /// Returns true if it was not 0, i.e. the buzzer was sounding
+    #[deprecated(note = "use `sound_active` to query and `tick_timers` to decrement")]
     pub fn sound_tick(&mut self) -> bool {
         if self.sound > 0 {
             self.sound -= 1;
@@ -319,99 +1476,143 @@ where
         false
     }
 
+    fn cpu_state(&self) -> CpuState {
+        CpuState {
+            I: self.I,
+            PC: self.PC,
+            delay: self.delay,
+            sound: self.sound,
+            SP: self.SP,
+            stack: self.stack,
+            hires: self.hires,
+            planes: self.planes,
+            audio_pattern: self.audio_pattern,
+            audio_pattern_set: self.audio_pattern_set,
+            pitch: self.pitch,
+            waiting_for_key: self.waiting_for_key,
+            halted: self.halted,
+        }
+    }
+
+    fn set_cpu_state(&mut self, cpu: CpuState) {
+        if cpu.hires != self.hires {
+            self.display_dirty = true;
+        }
+        self.I = cpu.I;
+        self.PC = cpu.PC;
+        self.delay = cpu.delay;
+        self.sound = cpu.sound;
+        self.SP = cpu.SP;
+        self.stack = cpu.stack;
+        self.hires = cpu.hires;
+        self.planes = cpu.planes;
+        self.audio_pattern = cpu.audio_pattern;
+        self.audio_pattern_set = cpu.audio_pattern_set;
+        self.pitch = cpu.pitch;
+        self.waiting_for_key = cpu.waiting_for_key;
+        // Presses seen by an interrupted LD Vx, K don't belong to the
+        // restored state, a restored wait collects them again
+        self.key_wait_pressed = 0;
+        self.halted = cpu.halted;
+    }
+
+    /// Save the memory, display, registers, timers and stack
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            memory: Clone::clone(&self.memory),
+            gfx: self.gfx,
+            V: self.V,
+            cpu: self.cpu_state(),
+        }
+    }
+
+    /// Return to the state saved by `snapshot`
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.memory.clone_from(&snapshot.memory);
+        self.gfx = snapshot.gfx;
+        self.V = snapshot.V;
+        self.set_cpu_state(snapshot.cpu);
+        self.display_dirty = true;
+    }
+
+    /// Changes that take a machine in the `other` state to the current one
+    ///
+    /// Only the memory bytes, display bytes and registers that differ are
+    /// stored, so deltas between consecutive frames stay small.
+    #[cfg(feature = "alloc")]
+    pub fn diff(&self, other: &Snapshot) -> StateDelta {
+        StateDelta {
+            memory: changed_entries(&other.memory, &self.memory),
+            gfx: changed_entries(&other.gfx, &self.gfx),
+            V: changed_entries(&other.V, &self.V),
+            cpu: self.cpu_state(),
+        }
+    }
+
+    /// Apply a delta from `diff`
+    ///
+    /// The machine must be in the state the delta was computed against,
+    /// otherwise the result is a mix of both. A delta that changes memory
+    /// past the end of this machine's memory is rejected before anything is
+    /// applied.
+    #[cfg(feature = "alloc")]
+    pub fn apply_delta(&mut self, delta: &StateDelta) -> Result<(), Chip8Error> {
+        if let Some(&(addr, _)) = delta
+            .memory
+            .iter()
+            .find(|&&(addr, _)| addr as usize >= self.memory.len())
+        {
+            return Err(Chip8Error::DeltaOutOfRange { addr });
+        }
+        for &(addr, value) in &delta.memory {
+            self.memory[addr as usize] = value;
+        }
+        for &(index, value) in &delta.gfx {
+            self.gfx[index as usize] = value;
+        }
+        for &(index, value) in &delta.V {
+            self.V[index as usize] = value;
+        }
+        if !delta.gfx.is_empty() {
+            self.display_dirty = true;
+        }
+        self.set_cpu_state(delta.cpu);
+        Ok(())
+    }
+
+    /// Returns the registers, stack, timers and next instruction
+    #[cfg(feature = "debug")]
+    pub fn debug_snapshot(&self) -> DebugSnapshot {
+        let opcode = self.peek_instruction();
+        DebugSnapshot {
+            pc: self.PC,
+            opcode,
+            registers: self.V,
+            i: self.I,
+            sp: self.SP,
+            stack: self.stack,
+            delay: self.delay,
+            sound: self.sound,
+            mnemonic: Self::print_instruction(opcode, &self.quirks),
+        }
+    }
+
     /// Returns PC, next instruction, registers and pointer register
     #[cfg(feature = "debug")]
     pub fn get_debug_info(&self) -> (u16, u16, [u8; 16], u16) {
-        (
-            self.PC,
-            ((self.memory[self.PC as usize] as u16) << 8)
-                + self.memory[(self.PC as usize) + 1] as u16,
-            self.V,
-            self.I,
-        )
+        let snapshot = self.debug_snapshot();
+        (snapshot.pc, snapshot.opcode, snapshot.registers, snapshot.i)
     }
 
     /// Print the opcode definition
     #[cfg(feature = "debug")]
-    pub fn print_instruction(instruction: u16) -> String {
-        let opcode = (
-            (instruction & 0xF000) >> 12,
-            (instruction & 0x0F00) >> 8,
-            (instruction & 0x00F0) >> 4,
-            instruction & 0x000F,
-        );
-
-        match opcode {
-            // CLS
-            (0, 0, 0xE, 0) => "CLS".to_string(),
-            // RET
-            (0, 0, 0xE, 0xE) => "RET".to_string(),
-            // JMP nnn
-            (1, _, _, _) => format!("JMP {:x}", instruction & 0x0FFF),
-            // CALL nnn
-            (2, _, _, _) => format!("CALL {:x}", instruction & 0x0FFF),
-            // SE Vx, byte
-            (3, x, _, _) => format!("SE V{}, {:x}", x, instruction & 0x00FF),
-            // SNE Vx, byte
-            (4, x, _, _) => format!("SNE V{}, {:x}", x, instruction & 0x00FF),
-            // SE Vx, Vy
-            (5, x, y, 0) => format!("SE V{}, V{}", x, y),
-            // LD Vx, byte
-            (6, x, _, _) => format!("LD V{}, {:x}", x, instruction & 0x00FF),
-            // ADD Vx, byte
-            (7, x, _, _) => format!("ADD V{}, {:x}", x, instruction & 0x00FF),
-            // LD Vx, Vy
-            (8, x, y, 0) => format!("LD V{}, V{}", x, y),
-            // OR Vx, Vy
-            (8, x, y, 1) => format!("OR V{}, V{}", x, y),
-            // AND Vx, Vy
-            (8, x, y, 2) => format!("AND V{}, V{}", x, y),
-            // XOR Vx, Vy
-            (8, x, y, 3) => format!("XOR V{}, V{}", x, y),
-            // ADD Vx, Vy
-            (8, x, y, 4) => format!("ADD V{}, V{}", x, y),
-            // SUB Vx, Vy
-            (8, x, y, 5) => format!("SUB V{}, V{}", x, y),
-            // SHR Vx, Vy
-            (8, x, y, 6) => format!("SHR V{}, V{}", x, y),
-            // SUBN Vx, Vy
-            (8, x, y, 7) => format!("SUBN V{}, V{}", x, y),
-            // SHL Vx, Vy
-            (8, x, y, 0xE) => format!("SHL V{}, V{}", x, y),
-            // SNE Vx, Vy
-            (9, x, y, 0) => format!("SNE V{}, V{}", x, y),
-            // LD I, addr
-            (0xA, _, _, _) => format!("LD I, {:x}", instruction & 0x0FFF),
-            // JP V0, addr
-            (0xB, _, _, _) => format!("JP V0, {:x}", instruction & 0x0FFF),
-            // RND Vx, byte
-            (0xC, x, _, _) => format!("RND V{}, {:x}", x, instruction & 0x00FF),
-            // DRW Vx, Vy, nibble
-            (0xD, x, y, n) => format!("DRW V{}, V{}, {:x}", x, y, n),
-            // SKP Vx
-            (0xE, x, 9, 0xE) => format!("SKP V{}", x),
-            // SKNP Vx
-            (0xE, x, 0xA, 1) => format!("SKNP V{}", x),
-            // LD Vx, DT
-            (0xF, x, 0, 7) => format!("LD V{}, DT", x),
-            // LD Vx, K
-            (0xF, x, 0, 0xA) => format!("LD V{}, K", x),
-            // LD DT, Vx
-            (0xF, x, 1, 5) => format!("LD DT, V{}", x),
-            // LD ST, Vx
-            (0xF, x, 1, 8) => format!("LD ST, V{}", x),
-            // ADD I, Vx
-            (0xF, x, 1, 0xE) => format!("ADD I, V{}", x),
-            // LD F, Vx
-            (0xF, x, 2, 9) => format!("LD F, V{}", x),
-            // LD B, Vx
-            (0xF, x, 3, 3) => format!("LD B, V{}", x),
-            // LD [I], Vx
-            (0xF, x, 5, 5) => format!("LD [I], V{}", x),
-            // LD Vx, [I]
-            (0xF, x, 6, 5) => format!("LD V{}, [I]", x),
-
-            (_, _, _, _) => "Invalid instruction".to_string(),
+    pub fn print_instruction(instruction: u16, quirks: &Quirks) -> String {
+        match decode(instruction) {
+            Some(Opcode::JpV0(addr)) if quirks.jump_with_vx => {
+                format!("JP V{}, {:x}", addr >> 8, addr)
+            }
+            Some(opcode) => opcode.to_string(),
+            None => "Invalid instruction".to_string(),
         }
     }
 }
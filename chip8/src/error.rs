@@ -0,0 +1,93 @@
+//! Errors returned by the emulator
+
+use core::fmt;
+
+use crate::opcode::Opcode;
+
+/// Errors that can occur while running or inspecting the machine
+///
+/// Non-exhaustive since some variants, like `Io`, only exist with a feature
+/// enabled and more may be added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Chip8Error {
+    /// A caller supplied buffer is smaller than required
+    BufferTooSmall { required: usize, actual: usize },
+    /// A caller supplied buffer doesn't have the expected length
+    BufferLengthMismatch { expected: usize, actual: usize },
+    /// The instruction does not decode to a known opcode
+    InvalidInstruction(u16),
+    /// CALL with all 16 stack entries in use
+    StackOverflow,
+    /// RET with an empty stack
+    StackUnderflow,
+    /// A CALL at `addr` nested deeper than the configured recursion limit
+    RecursionLimitExceeded { addr: u16 },
+    /// The opcode family was disabled with `disable_opcode`
+    NotImplementedYet(Opcode),
+    /// The ROM is `size` bytes but only `max` fit in memory
+    RomTooLarge { size: usize, max: usize },
+    /// The builder was given a memory size outside 4K to 64K
+    InvalidMemorySize { size: usize },
+    /// FX33 or FX55 tried to write the protected ROM at `addr`
+    RomWrite { addr: u16 },
+    /// Strict alignment is on and PC points at an odd address
+    MisalignedPc { pc: u16 },
+    /// A state delta changes memory at `addr`, past the end of this machine's
+    /// memory
+    DeltaOutOfRange { addr: u16 },
+    /// Reading the ROM or writing the trace failed
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::BufferTooSmall { required, actual } => write!(
+                f,
+                "buffer too small: {} bytes required, got {}",
+                required, actual
+            ),
+            Chip8Error::BufferLengthMismatch { expected, actual } => write!(
+                f,
+                "buffer length mismatch: expected {} bytes, got {}",
+                expected, actual
+            ),
+            Chip8Error::InvalidInstruction(instruction) => {
+                write!(f, "invalid instruction {:04x}", instruction)
+            }
+            Chip8Error::StackOverflow => write!(f, "stack overflow"),
+            Chip8Error::StackUnderflow => write!(f, "stack underflow"),
+            Chip8Error::RecursionLimitExceeded { addr } => {
+                write!(f, "recursion limit exceeded by CALL at {:03x}", addr)
+            }
+            Chip8Error::NotImplementedYet(opcode) => {
+                write!(
+                    f,
+                    "{} ({}) is not implemented yet",
+                    opcode,
+                    opcode.pattern()
+                )
+            }
+            Chip8Error::RomTooLarge { size, max } => {
+                write!(f, "ROM too large: {} bytes, at most {} fit", size, max)
+            }
+            Chip8Error::InvalidMemorySize { size } => {
+                write!(f, "invalid memory size {}, must be 4096 to 65536", size)
+            }
+            Chip8Error::RomWrite { addr } => {
+                write!(f, "write to protected ROM at {:03x}", addr)
+            }
+            Chip8Error::MisalignedPc { pc } => write!(f, "misaligned PC {:03x}", pc),
+            Chip8Error::DeltaOutOfRange { addr } => {
+                write!(f, "state delta writes {:04x}, past the end of memory", addr)
+            }
+            #[cfg(feature = "std")]
+            Chip8Error::Io(kind) => write!(f, "I/O error: {}", kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Chip8Error {}
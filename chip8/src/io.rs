@@ -4,3 +4,32 @@
 pub trait Random {
     fn randint(&mut self) -> u8;
 }
+
+/// Deterministic random source, the same seed always gives the same bytes
+///
+/// A small xorshift64* generator for tests and reproducible runs. The SDL
+/// frontend keeps using the `rand` crate for its generator.
+#[derive(Debug, Clone)]
+pub struct SeededRandom {
+    state: u64,
+}
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> Self {
+        // xorshift never leaves an all zero state, so scramble the seed
+        let state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        SeededRandom {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+}
+
+impl Random for SeededRandom {
+    fn randint(&mut self) -> u8 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        // The high bits are the best mixed
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+}
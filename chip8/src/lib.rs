@@ -1,4 +1,26 @@
 //! CHIP-8 emulator library
+//!
+//! The core is `no_std` compatible. The default `std` feature enables the
+//! `std::error::Error` implementation and std only helpers. On a bare-metal
+//! target disable the default features:
+//!
+//! ```toml
+//! chip8 = { version = "0.1", default-features = false }
+//! ```
+//!
+//! and enable `alloc` if a global allocator is available, for APIs that
+//! return `String` or `Vec`. The `debug` feature requires `alloc`.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod analyze;
+#[cfg(feature = "alloc")]
+pub mod asm;
 pub mod chip8;
+pub mod error;
 pub mod io;
+pub mod opcode;
+pub mod quirks;
@@ -0,0 +1,353 @@
+//! Instruction decoding
+
+use core::fmt;
+
+use crate::quirks::Mode;
+
+/// A decoded CHIP-8 instruction
+///
+/// `x` and `y` are register indices, `addr` is a 12 bit address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    /// 00E0
+    Cls,
+    /// 00EE
+    Ret,
+    /// 1nnn
+    Jmp(u16),
+    /// 2nnn
+    Call(u16),
+    /// 3xkk
+    SeVxByte { x: u8, byte: u8 },
+    /// 4xkk
+    SneVxByte { x: u8, byte: u8 },
+    /// 5xy0
+    SeVxVy { x: u8, y: u8 },
+    /// 6xkk
+    LdVxByte { x: u8, byte: u8 },
+    /// 7xkk
+    AddVxByte { x: u8, byte: u8 },
+    /// 8xy0
+    LdVxVy { x: u8, y: u8 },
+    /// 8xy1
+    Or { x: u8, y: u8 },
+    /// 8xy2
+    And { x: u8, y: u8 },
+    /// 8xy3
+    Xor { x: u8, y: u8 },
+    /// 8xy4
+    AddVxVy { x: u8, y: u8 },
+    /// 8xy5
+    Sub { x: u8, y: u8 },
+    /// 8xy6
+    Shr { x: u8, y: u8 },
+    /// 8xy7
+    Subn { x: u8, y: u8 },
+    /// 8xyE
+    Shl { x: u8, y: u8 },
+    /// 9xy0
+    SneVxVy { x: u8, y: u8 },
+    /// Annn
+    LdI(u16),
+    /// Bnnn
+    JpV0(u16),
+    /// Cxkk
+    Rnd { x: u8, byte: u8 },
+    /// Dxyn
+    Drw { x: u8, y: u8, n: u8 },
+    /// Ex9E
+    Skp { x: u8 },
+    /// ExA1
+    Sknp { x: u8 },
+    /// Fx07
+    LdVxDt { x: u8 },
+    /// Fx0A
+    LdVxK { x: u8 },
+    /// Fx15
+    LdDtVx { x: u8 },
+    /// Fx18
+    LdStVx { x: u8 },
+    /// Fx1E
+    AddIVx { x: u8 },
+    /// Fx29
+    LdFVx { x: u8 },
+    /// Fx33
+    LdBVx { x: u8 },
+    /// Fx55
+    LdIVx { x: u8 },
+    /// Fx65
+    LdVxI { x: u8 },
+    /// F000 NNNN, XO-CHIP only, the address is the following word
+    LdILong,
+    /// Fn01, XO-CHIP only, `n` is a bitmask of the planes to draw on
+    Plane(u8),
+    /// 00FE, SUPER-CHIP, switch to 64x32
+    LoRes,
+    /// 00FF, SUPER-CHIP, switch to 128x64
+    HiRes,
+    /// F002, XO-CHIP only, load the 16 byte audio pattern from I
+    Audio,
+    /// Fx3A, XO-CHIP only, set the audio pattern playback pitch
+    Pitch { x: u8 },
+}
+
+/// Patterns of every opcode family, in the order of the `Opcode` variants
+pub const PATTERNS: [&str; 40] = [
+    "00E0", "00EE", "1nnn", "2nnn", "3xkk", "4xkk", "5xy0", "6xkk", "7xkk", "8xy0", "8xy1", "8xy2",
+    "8xy3", "8xy4", "8xy5", "8xy6", "8xy7", "8xyE", "9xy0", "Annn", "Bnnn", "Cxkk", "Dxyn", "Ex9E",
+    "ExA1", "Fx07", "Fx0A", "Fx15", "Fx18", "Fx1E", "Fx29", "Fx33", "Fx55", "Fx65", "F000", "Fn01",
+    "00FE", "00FF", "F002", "Fx3A",
+];
+
+/// Decode a 16 bit instruction, returns None if it is not a valid opcode
+pub fn decode(instruction: u16) -> Option<Opcode> {
+    // split into nibbles as the opcodes are based on nibbles
+    let nibbles = (
+        ((instruction & 0xF000) >> 12) as u8,
+        ((instruction & 0x0F00) >> 8) as u8,
+        ((instruction & 0x00F0) >> 4) as u8,
+        (instruction & 0x000F) as u8,
+    );
+    let addr = instruction & 0x0FFF;
+    let byte = (instruction & 0x00FF) as u8;
+
+    let opcode = match nibbles {
+        (0, 0, 0xE, 0) => Opcode::Cls,
+        (0, 0, 0xE, 0xE) => Opcode::Ret,
+        (0, 0, 0xF, 0xE) => Opcode::LoRes,
+        (0, 0, 0xF, 0xF) => Opcode::HiRes,
+        (1, _, _, _) => Opcode::Jmp(addr),
+        (2, _, _, _) => Opcode::Call(addr),
+        (3, x, _, _) => Opcode::SeVxByte { x, byte },
+        (4, x, _, _) => Opcode::SneVxByte { x, byte },
+        (5, x, y, 0) => Opcode::SeVxVy { x, y },
+        (6, x, _, _) => Opcode::LdVxByte { x, byte },
+        (7, x, _, _) => Opcode::AddVxByte { x, byte },
+        (8, x, y, 0) => Opcode::LdVxVy { x, y },
+        (8, x, y, 1) => Opcode::Or { x, y },
+        (8, x, y, 2) => Opcode::And { x, y },
+        (8, x, y, 3) => Opcode::Xor { x, y },
+        (8, x, y, 4) => Opcode::AddVxVy { x, y },
+        (8, x, y, 5) => Opcode::Sub { x, y },
+        (8, x, y, 6) => Opcode::Shr { x, y },
+        (8, x, y, 7) => Opcode::Subn { x, y },
+        (8, x, y, 0xE) => Opcode::Shl { x, y },
+        (9, x, y, 0) => Opcode::SneVxVy { x, y },
+        (0xA, _, _, _) => Opcode::LdI(addr),
+        (0xB, _, _, _) => Opcode::JpV0(addr),
+        (0xC, x, _, _) => Opcode::Rnd { x, byte },
+        (0xD, x, y, n) => Opcode::Drw { x, y, n },
+        (0xE, x, 9, 0xE) => Opcode::Skp { x },
+        (0xE, x, 0xA, 1) => Opcode::Sknp { x },
+        (0xF, x, 0, 7) => Opcode::LdVxDt { x },
+        (0xF, x, 0, 0xA) => Opcode::LdVxK { x },
+        (0xF, x, 1, 5) => Opcode::LdDtVx { x },
+        (0xF, x, 1, 8) => Opcode::LdStVx { x },
+        (0xF, x, 1, 0xE) => Opcode::AddIVx { x },
+        (0xF, x, 2, 9) => Opcode::LdFVx { x },
+        (0xF, x, 3, 3) => Opcode::LdBVx { x },
+        (0xF, x, 5, 5) => Opcode::LdIVx { x },
+        (0xF, x, 6, 5) => Opcode::LdVxI { x },
+        (0xF, 0, 0, 0) => Opcode::LdILong,
+        (0xF, n, 0, 1) => Opcode::Plane(n),
+        (0xF, 0, 0, 2) => Opcode::Audio,
+        (0xF, x, 3, 0xA) => Opcode::Pitch { x },
+
+        (_, _, _, _) => return None,
+    };
+
+    Some(opcode)
+}
+
+impl Opcode {
+    /// Index of the opcode family in `PATTERNS`
+    pub fn family(self) -> usize {
+        match self {
+            Opcode::Cls => 0,
+            Opcode::Ret => 1,
+            Opcode::Jmp(_) => 2,
+            Opcode::Call(_) => 3,
+            Opcode::SeVxByte { .. } => 4,
+            Opcode::SneVxByte { .. } => 5,
+            Opcode::SeVxVy { .. } => 6,
+            Opcode::LdVxByte { .. } => 7,
+            Opcode::AddVxByte { .. } => 8,
+            Opcode::LdVxVy { .. } => 9,
+            Opcode::Or { .. } => 10,
+            Opcode::And { .. } => 11,
+            Opcode::Xor { .. } => 12,
+            Opcode::AddVxVy { .. } => 13,
+            Opcode::Sub { .. } => 14,
+            Opcode::Shr { .. } => 15,
+            Opcode::Subn { .. } => 16,
+            Opcode::Shl { .. } => 17,
+            Opcode::SneVxVy { .. } => 18,
+            Opcode::LdI(_) => 19,
+            Opcode::JpV0(_) => 20,
+            Opcode::Rnd { .. } => 21,
+            Opcode::Drw { .. } => 22,
+            Opcode::Skp { .. } => 23,
+            Opcode::Sknp { .. } => 24,
+            Opcode::LdVxDt { .. } => 25,
+            Opcode::LdVxK { .. } => 26,
+            Opcode::LdDtVx { .. } => 27,
+            Opcode::LdStVx { .. } => 28,
+            Opcode::AddIVx { .. } => 29,
+            Opcode::LdFVx { .. } => 30,
+            Opcode::LdBVx { .. } => 31,
+            Opcode::LdIVx { .. } => 32,
+            Opcode::LdVxI { .. } => 33,
+            Opcode::LdILong => 34,
+            Opcode::Plane(_) => 35,
+            Opcode::LoRes => 36,
+            Opcode::HiRes => 37,
+            Opcode::Audio => 38,
+            Opcode::Pitch { .. } => 39,
+        }
+    }
+
+    /// Returns true if the opcode exists in the given instruction set
+    pub fn available_in(self, mode: Mode) -> bool {
+        match self {
+            Opcode::LdILong | Opcode::Plane(_) | Opcode::Audio | Opcode::Pitch { .. } => {
+                mode == Mode::XoChip
+            }
+            Opcode::LoRes | Opcode::HiRes => mode != Mode::Chip8,
+            _ => true,
+        }
+    }
+
+    /// Pattern of the opcode family, e.g. `8xy4`
+    pub fn pattern(self) -> &'static str {
+        PATTERNS[self.family()]
+    }
+
+    /// Returns true if executing the opcode reads register `r`
+    ///
+    /// BNNN is assumed to read V0.
+    pub fn reads_register(self, r: u8) -> bool {
+        match self {
+            Opcode::SeVxByte { x, .. }
+            | Opcode::SneVxByte { x, .. }
+            | Opcode::AddVxByte { x, .. }
+            | Opcode::Skp { x }
+            | Opcode::Sknp { x }
+            | Opcode::LdDtVx { x }
+            | Opcode::LdStVx { x }
+            | Opcode::AddIVx { x }
+            | Opcode::LdFVx { x }
+            | Opcode::LdBVx { x }
+            | Opcode::Pitch { x } => x == r,
+            Opcode::SeVxVy { x, y }
+            | Opcode::SneVxVy { x, y }
+            | Opcode::Or { x, y }
+            | Opcode::And { x, y }
+            | Opcode::Xor { x, y }
+            | Opcode::AddVxVy { x, y }
+            | Opcode::Sub { x, y }
+            | Opcode::Subn { x, y }
+            | Opcode::Drw { x, y, .. } => x == r || y == r,
+            Opcode::LdVxVy { y, .. } | Opcode::Shr { y, .. } | Opcode::Shl { y, .. } => y == r,
+            Opcode::LdIVx { x } => r <= x,
+            Opcode::JpV0(_) => r == 0,
+            _ => false,
+        }
+    }
+
+    /// Encode the opcode back into its 16 bit instruction
+    pub fn encode(self) -> u16 {
+        let xy = |op: u16, x: u8, y: u8, n: u16| op << 12 | (x as u16) << 8 | (y as u16) << 4 | n;
+        let xkk = |op: u16, x: u8, byte: u8| op << 12 | (x as u16) << 8 | byte as u16;
+
+        match self {
+            Opcode::Cls => 0x00E0,
+            Opcode::Ret => 0x00EE,
+            Opcode::Jmp(addr) => 0x1000 | addr,
+            Opcode::Call(addr) => 0x2000 | addr,
+            Opcode::SeVxByte { x, byte } => xkk(3, x, byte),
+            Opcode::SneVxByte { x, byte } => xkk(4, x, byte),
+            Opcode::SeVxVy { x, y } => xy(5, x, y, 0),
+            Opcode::LdVxByte { x, byte } => xkk(6, x, byte),
+            Opcode::AddVxByte { x, byte } => xkk(7, x, byte),
+            Opcode::LdVxVy { x, y } => xy(8, x, y, 0),
+            Opcode::Or { x, y } => xy(8, x, y, 1),
+            Opcode::And { x, y } => xy(8, x, y, 2),
+            Opcode::Xor { x, y } => xy(8, x, y, 3),
+            Opcode::AddVxVy { x, y } => xy(8, x, y, 4),
+            Opcode::Sub { x, y } => xy(8, x, y, 5),
+            Opcode::Shr { x, y } => xy(8, x, y, 6),
+            Opcode::Subn { x, y } => xy(8, x, y, 7),
+            Opcode::Shl { x, y } => xy(8, x, y, 0xE),
+            Opcode::SneVxVy { x, y } => xy(9, x, y, 0),
+            Opcode::LdI(addr) => 0xA000 | addr,
+            Opcode::JpV0(addr) => 0xB000 | addr,
+            Opcode::Rnd { x, byte } => xkk(0xC, x, byte),
+            Opcode::Drw { x, y, n } => xy(0xD, x, y, n as u16),
+            Opcode::Skp { x } => xkk(0xE, x, 0x9E),
+            Opcode::Sknp { x } => xkk(0xE, x, 0xA1),
+            Opcode::LdVxDt { x } => xkk(0xF, x, 0x07),
+            Opcode::LdVxK { x } => xkk(0xF, x, 0x0A),
+            Opcode::LdDtVx { x } => xkk(0xF, x, 0x15),
+            Opcode::LdStVx { x } => xkk(0xF, x, 0x18),
+            Opcode::AddIVx { x } => xkk(0xF, x, 0x1E),
+            Opcode::LdFVx { x } => xkk(0xF, x, 0x29),
+            Opcode::LdBVx { x } => xkk(0xF, x, 0x33),
+            Opcode::LdIVx { x } => xkk(0xF, x, 0x55),
+            Opcode::LdVxI { x } => xkk(0xF, x, 0x65),
+            Opcode::LdILong => 0xF000,
+            Opcode::Plane(n) => xkk(0xF, n, 0x01),
+            Opcode::LoRes => 0x00FE,
+            Opcode::HiRes => 0x00FF,
+            Opcode::Audio => 0xF002,
+            Opcode::Pitch { x } => xkk(0xF, x, 0x3A),
+        }
+    }
+}
+
+/// Formats the opcode as an assembly mnemonic
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Opcode::Cls => write!(f, "CLS"),
+            Opcode::Ret => write!(f, "RET"),
+            Opcode::Jmp(addr) => write!(f, "JMP {:x}", addr),
+            Opcode::Call(addr) => write!(f, "CALL {:x}", addr),
+            Opcode::SeVxByte { x, byte } => write!(f, "SE V{}, {:x}", x, byte),
+            Opcode::SneVxByte { x, byte } => write!(f, "SNE V{}, {:x}", x, byte),
+            Opcode::SeVxVy { x, y } => write!(f, "SE V{}, V{}", x, y),
+            Opcode::LdVxByte { x, byte } => write!(f, "LD V{}, {:x}", x, byte),
+            Opcode::AddVxByte { x, byte } => write!(f, "ADD V{}, {:x}", x, byte),
+            Opcode::LdVxVy { x, y } => write!(f, "LD V{}, V{}", x, y),
+            Opcode::Or { x, y } => write!(f, "OR V{}, V{}", x, y),
+            Opcode::And { x, y } => write!(f, "AND V{}, V{}", x, y),
+            Opcode::Xor { x, y } => write!(f, "XOR V{}, V{}", x, y),
+            Opcode::AddVxVy { x, y } => write!(f, "ADD V{}, V{}", x, y),
+            Opcode::Sub { x, y } => write!(f, "SUB V{}, V{}", x, y),
+            Opcode::Shr { x, y } => write!(f, "SHR V{}, V{}", x, y),
+            Opcode::Subn { x, y } => write!(f, "SUBN V{}, V{}", x, y),
+            Opcode::Shl { x, y } => write!(f, "SHL V{}, V{}", x, y),
+            Opcode::SneVxVy { x, y } => write!(f, "SNE V{}, V{}", x, y),
+            Opcode::LdI(addr) => write!(f, "LD I, {:x}", addr),
+            Opcode::JpV0(addr) => write!(f, "JP V0, {:x}", addr),
+            Opcode::Rnd { x, byte } => write!(f, "RND V{}, {:x}", x, byte),
+            Opcode::Drw { x, y, n } => write!(f, "DRW V{}, V{}, {:x}", x, y, n),
+            Opcode::Skp { x } => write!(f, "SKP V{}", x),
+            Opcode::Sknp { x } => write!(f, "SKNP V{}", x),
+            Opcode::LdVxDt { x } => write!(f, "LD V{}, DT", x),
+            Opcode::LdVxK { x } => write!(f, "LD V{}, K", x),
+            Opcode::LdDtVx { x } => write!(f, "LD DT, V{}", x),
+            Opcode::LdStVx { x } => write!(f, "LD ST, V{}", x),
+            Opcode::AddIVx { x } => write!(f, "ADD I, V{}", x),
+            Opcode::LdFVx { x } => write!(f, "LD F, V{}", x),
+            Opcode::LdBVx { x } => write!(f, "LD B, V{}", x),
+            Opcode::LdIVx { x } => write!(f, "LD [I], V{}", x),
+            Opcode::LdVxI { x } => write!(f, "LD V{}, [I]", x),
+            Opcode::LdILong => write!(f, "LD I, LONG"),
+            Opcode::Plane(n) => write!(f, "PLANE {:x}", n),
+            Opcode::LoRes => write!(f, "LOW"),
+            Opcode::HiRes => write!(f, "HIGH"),
+            Opcode::Audio => write!(f, "AUDIO"),
+            Opcode::Pitch { x } => write!(f, "PITCH V{}", x),
+        }
+    }
+}
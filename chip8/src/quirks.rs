@@ -0,0 +1,119 @@
+//! Behaviour differences between CHIP-8 interpreters
+
+/// Interpreter quirks, the default matches the original COSMAC VIP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// BNNN jumps to NNN + VX, where X is the high nibble of NNN (BXNN),
+    /// instead of NNN + V0
+    pub jump_with_vx: bool,
+    /// DRW wraps sprite pixels that run off the screen edge to the opposite
+    /// side instead of clipping them
+    pub wrap_sprites: bool,
+    /// ADD I, Vx sets VF to 1 when I passes 0x0FFF and to 0 otherwise, like
+    /// the Amiga interpreter. When off VF is left untouched.
+    pub add_i_sets_vf: bool,
+    /// DRW with n = 0 draws a 16x16 sprite in low resolution too, instead of
+    /// nothing. High resolution always draws 16x16.
+    pub lores_large_sprites: bool,
+    /// DRW waits for the vertical blank like on the COSMAC VIP, so
+    /// `run_cycles` and `advance` end the frame right after it and at most
+    /// one sprite is drawn per frame. Off in every preset, few ROMs other
+    /// than some VIP games depend on it.
+    pub display_wait: bool,
+    /// Instruction set extensions on top of CHIP-8
+    pub mode: Mode,
+}
+
+impl Quirks {
+    /// Original COSMAC VIP interpreter
+    pub const fn cosmac_vip() -> Self {
+        Quirks {
+            jump_with_vx: false,
+            wrap_sprites: false,
+            add_i_sets_vf: false,
+            lores_large_sprites: false,
+            display_wait: false,
+            mode: Mode::Chip8,
+        }
+    }
+
+    /// CHIP-48 for the HP-48 calculators, the predecessor of SUPER-CHIP
+    pub const fn chip48() -> Self {
+        Quirks {
+            jump_with_vx: true,
+            wrap_sprites: false,
+            add_i_sets_vf: false,
+            lores_large_sprites: false,
+            display_wait: false,
+            mode: Mode::Chip8,
+        }
+    }
+
+    /// SUPER-CHIP interpreter
+    pub const fn schip() -> Self {
+        Quirks {
+            jump_with_vx: true,
+            wrap_sprites: false,
+            add_i_sets_vf: false,
+            lores_large_sprites: false,
+            display_wait: false,
+            mode: Mode::SuperChip,
+        }
+    }
+
+    /// XO-CHIP, as implemented by Octo
+    pub const fn xo_chip() -> Self {
+        Quirks {
+            jump_with_vx: false,
+            wrap_sprites: true,
+            add_i_sets_vf: false,
+            lores_large_sprites: true,
+            display_wait: false,
+            mode: Mode::XoChip,
+        }
+    }
+}
+
+/// Every preset with a short name, in historical order
+pub const PRESETS: [(&str, Quirks); 4] = [
+    ("vip", Quirks::cosmac_vip()),
+    ("chip48", Quirks::chip48()),
+    ("schip", Quirks::schip()),
+    ("xo-chip", Quirks::xo_chip()),
+];
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::cosmac_vip()
+    }
+}
+
+/// Instruction set supported by the interpreter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// The original instruction set
+    Chip8,
+    /// SUPER-CHIP: 128x64 high resolution mode (00FE, 00FF)
+    SuperChip,
+    /// XO-CHIP: the SUPER-CHIP additions, two drawing planes (FN01), 16 bit
+    /// I loads (F000 NNNN) and the audio pattern buffer (F002, FX3A)
+    ///
+    /// Selecting this mode doesn't change the memory size, it stays at
+    /// `DEFAULT_MEMORY_SIZE` (4K) unless the builder's `memory_size` raises
+    /// it, up to the 64K Octo provides. Long addresses wrap at the end of
+    /// whatever size is configured.
+    XoChip,
+}
+
+/// A pattern suggesting the ROM relies on a quirk that is currently disabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuirkWarning {
+    /// VF was read right after ADD I, Vx, the ROM probably expects the carry
+    /// from `add_i_sets_vf`
+    AddISetsVf,
+}
+
+impl QuirkWarning {
+    /// Every warning kind
+    pub const ALL: [QuirkWarning; 1] = [QuirkWarning::AddISetsVf];
+}
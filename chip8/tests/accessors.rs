@@ -0,0 +1,230 @@
+mod common;
+
+use chip8::chip8::{Chip8, ETI_660_ROM_OFFSET};
+use chip8::error::Chip8Error;
+use chip8::opcode::decode;
+use common::FixedRandom;
+
+#[test]
+fn memory_access() {
+    let mut machine = Chip8::new(&[0x12, 0x34], FixedRandom(0)).unwrap();
+
+    assert_eq!(machine.read_memory(0x200), 0x12);
+    assert_eq!(machine.read_memory(0x201), 0x34);
+    // fontset starts with the "0" glyph
+    assert_eq!(machine.read_memory(0), 0xF0);
+
+    machine.write_memory(0x300, 0xAB);
+    assert_eq!(machine.read_memory(0x300), 0xAB);
+
+    // out of range accesses don't panic
+    machine.write_memory(0x1000, 0xFF);
+    assert_eq!(machine.read_memory(0x1000), 0);
+}
+
+#[test]
+fn register_access() {
+    // LD I, 345
+    let mut machine = Chip8::new(&[0xA3, 0x45], FixedRandom(0)).unwrap();
+    assert_eq!(machine.program_counter(), 0x200);
+
+    machine.set_register(3, 7);
+    machine.set_register(16, 1);
+    assert_eq!(machine.registers()[3], 7);
+
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.program_counter(), 0x202);
+    assert_eq!(machine.index_register(), 0x345);
+}
+
+#[test]
+fn instruction_count() {
+    // LD V0, 1; RET
+    let mut machine = Chip8::new(&[0x60, 0x01, 0x00, 0xEE], FixedRandom(0)).unwrap();
+    assert_eq!(machine.instruction_count(), 0);
+
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.instruction_count(), 1);
+
+    // failing instructions are not counted
+    assert!(machine.execute_instruction().is_err());
+    assert_eq!(machine.instruction_count(), 1);
+}
+
+#[test]
+fn opcode_histogram_and_reset() {
+    // LD V0, 1; ADD V0, 1; ADD V0, 1; LD F, V0; DRW V0, V0, 5
+    let rom = [0x60, 0x01, 0x70, 0x01, 0x70, 0x01, 0xF0, 0x29, 0xD0, 0x05];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+    for _ in 0..5 {
+        machine.execute_instruction().unwrap();
+    }
+
+    let histogram = machine.opcode_histogram();
+    assert_eq!(histogram[0x6], 1);
+    assert_eq!(histogram[0x7], 2);
+    assert_eq!(histogram[0xF], 1);
+    assert_eq!(histogram[0xD], 1);
+    assert_eq!(histogram.iter().sum::<u64>(), machine.instruction_count());
+
+    machine.reset();
+    assert_eq!(machine.instruction_count(), 0);
+    assert_eq!(machine.opcode_histogram(), &[0; 16]);
+    assert_eq!(machine.program_counter(), 0x200);
+    assert_eq!(machine.registers(), &[0; 16]);
+    assert!(machine.get_display().iter().all(|&pixel| pixel == 0));
+    // The program is still loaded
+    assert_eq!(machine.read_memory(0x200), 0x60);
+}
+
+#[test]
+fn load_rom_replaces_program_and_resets() {
+    // LD V0, 1
+    let mut machine = Chip8::new(&[0x60, 0x01, 0x60, 0x02], FixedRandom(0)).unwrap();
+    machine.execute_instruction().unwrap();
+    machine.write_memory(0x000, 0xAA);
+
+    // LD V1, 7
+    machine.load_rom(&[0x61, 0x07]).unwrap();
+    assert_eq!(machine.program_counter(), 0x200);
+    assert_eq!(machine.registers(), &[0; 16]);
+    assert_eq!(machine.instruction_count(), 0);
+    // The fontset is restored and the old program is gone
+    assert_eq!(machine.read_memory(0x000), 0xF0);
+    assert_eq!(machine.read_memory(0x202), 0x00);
+
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.registers()[1], 7);
+}
+
+#[test]
+fn oversized_roms_are_rejected() {
+    let rom = [0x12; 4096 - 0x200 + 1];
+    assert_eq!(
+        Chip8::new(&rom, FixedRandom(0)).err(),
+        Some(Chip8Error::RomTooLarge {
+            size: rom.len(),
+            max: 4096 - 0x200
+        })
+    );
+
+    let mut machine = Chip8::new(&[0x34; 16], FixedRandom(0)).unwrap();
+    assert!(machine.load_rom(&rom).is_err());
+    // A failed load leaves the machine alone
+    assert_eq!(machine.read_memory(0x200), 0x34);
+
+    // A ROM filling all of memory fits
+    assert!(machine.load_rom(&rom[1..]).is_ok());
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn dump_memory_formats_hex_and_ascii() {
+    let rom = b"CHIP-8 hex dump!\x00\x01\x7f\xff";
+    let machine = Chip8::new(rom, FixedRandom(0)).unwrap();
+
+    assert_eq!(
+        machine.dump_memory(0x200, 32),
+        "0200  43 48 49 50 2d 38 20 68 65 78 20 64 75 6d 70 21  |CHIP-8 hex dump!|\n\
+         0210  00 01 7f ff 00 00 00 00 00 00 00 00 00 00 00 00  |................|\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn disassemble_to_writes_a_listing() {
+    // CLS; LD V0, 42; LD I, 20c; DRW V0, V1, 5; JMP 20a, then a data word
+    let rom = [
+        0x00, 0xE0, 0x60, 0x42, 0xA2, 0x0C, 0xD0, 0x15, 0x12, 0x0A, 0xFF, 0xFF, 0x7E,
+    ];
+    let machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+
+    let mut listing = Vec::new();
+    machine.disassemble_to(&mut listing, 0x200, 0x20D).unwrap();
+    assert_eq!(
+        String::from_utf8(listing).unwrap(),
+        "0200: 00e0  CLS\n\
+         0202: 6042  LD V0, 42\n\
+         0204: a20c  LD I, 20c\n\
+         0206: d015  DRW V0, V1, 5\n\
+         0208: 120a  JMP 20a\n\
+         020a: ffff  DB 0xffff\n\
+         020c: 7e    DB 0x7e\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn dump_memory_clamps_to_memory() {
+    let machine = Chip8::new(&[], FixedRandom(0)).unwrap();
+
+    assert_eq!(
+        machine.dump_memory(0xFFC, 32),
+        "0ffc  00 00 00 00                                      |....|\n"
+    );
+    assert_eq!(machine.dump_memory(0x1000, 16), "");
+}
+
+#[test]
+fn eti_660_load_address() {
+    // LD V0, 0x42
+    let mut machine =
+        Chip8::with_load_address(&[0x60, 0x42], FixedRandom(0), ETI_660_ROM_OFFSET as u16).unwrap();
+    assert_eq!(machine.program_counter(), 0x600);
+    assert_eq!(machine.read_memory(0x600), 0x60);
+    assert_eq!(machine.read_memory(0x200), 0x00);
+
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.registers()[0], 0x42);
+    assert_eq!(machine.program_counter(), 0x602);
+
+    // Reset returns to the load address
+    machine.reset();
+    assert_eq!(machine.program_counter(), 0x600);
+
+    // Less room is left above 0x600
+    assert_eq!(
+        machine.load_rom(&[0; 4096 - 0x600 + 1]),
+        Err(Chip8Error::RomTooLarge {
+            size: 4096 - 0x600 + 1,
+            max: 4096 - 0x600
+        })
+    );
+}
+
+#[test]
+fn keyboard_state() {
+    let mut machine = Chip8::new(&[], FixedRandom(0)).unwrap();
+
+    assert!(machine.set_key(0x3, true));
+    assert!(machine.set_key(0xF, true));
+    assert!(machine.set_key(0x3, false));
+    // Keys past F are rejected without panicking
+    assert!(!machine.set_key(16, true));
+    assert!(!machine.set_key(u8::MAX, true));
+
+    let mut expected = [false; 16];
+    expected[0xF] = true;
+    assert_eq!(machine.keyboard_state(), &expected);
+}
+
+#[test]
+fn peek_instruction_has_no_side_effects() {
+    // LD V0, 0x42; LD I, 0x123
+    let mut machine = Chip8::new(&[0x60, 0x42, 0xA1, 0x23], FixedRandom(0)).unwrap();
+
+    for _ in 0..3 {
+        assert_eq!(machine.peek_instruction(), 0x6042);
+        assert_eq!(machine.program_counter(), 0x200);
+    }
+    assert_eq!(
+        decode(machine.peek_instruction()).unwrap().to_string(),
+        "LD V0, 42"
+    );
+
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.registers()[0], 0x42);
+    assert_eq!(machine.peek_instruction(), 0xA123);
+    assert_eq!(machine.program_counter(), 0x202);
+    assert_eq!(machine.index_register(), 0);
+}
@@ -0,0 +1,69 @@
+use chip8::analyze::analyze_rom;
+use chip8::opcode::Opcode;
+use chip8::quirks::Mode;
+
+#[test]
+fn counts_families_and_flags_extensions() {
+    // CALL 208; SCD 4; JP 204; then at 208: DRW V0, V1, 5; DRW V0, V1, 5; RET
+    let rom = [
+        0x22, 0x08, 0x00, 0xC4, 0x12, 0x04, 0x00, 0x00, 0xD0, 0x15, 0xD0, 0x15, 0x00, 0xEE,
+    ];
+    let report = analyze_rom(&rom);
+
+    let drw = Opcode::Drw { x: 0, y: 0, n: 0 }.family();
+    let call = Opcode::Call(0).family();
+    assert_eq!(report.count(drw), 2);
+    assert_eq!(report.count(call), 1);
+    assert_eq!(
+        report.families().collect::<Vec<_>>(),
+        [
+            ("00EE", 1),
+            ("1nnn", 1),
+            ("2nnn", 1),
+            ("Dxyn", 2),
+            ("00Cn", 1)
+        ]
+    );
+
+    // The scroll is SUPER-CHIP even though the core can't run it yet, only
+    // the 0000 padding is unknown
+    assert_eq!(report.unknown(), 1);
+    assert_eq!(report.unimplemented().collect::<Vec<_>>(), ["00Cn"]);
+    assert_eq!(
+        report.unavailable_in(Mode::Chip8).collect::<Vec<_>>(),
+        ["00Cn"]
+    );
+    assert_eq!(report.unavailable_in(Mode::SuperChip).count(), 0);
+    assert_eq!(report.required_mode(), Mode::SuperChip);
+}
+
+#[test]
+fn recognizes_every_unimplemented_schip_encoding() {
+    // SCR; SCL; EXIT; LD HF, V3; LD R, V3; LD V3, R
+    let report = analyze_rom(&[
+        0x00, 0xFB, 0x00, 0xFC, 0x00, 0xFD, 0xF3, 0x30, 0xF3, 0x75, 0xF3, 0x85,
+    ]);
+    assert_eq!(report.unknown(), 0);
+    assert_eq!(report.unimplemented().count(), 6);
+    assert_eq!(report.required_mode(), Mode::SuperChip);
+}
+
+#[test]
+fn long_load_skips_its_address() {
+    // LD I, LONG 0x00E0; PLANE 3; odd trailing byte
+    let report = analyze_rom(&[0xF0, 0x00, 0x00, 0xE0, 0xF3, 0x01, 0x12]);
+
+    assert_eq!(report.count(Opcode::Cls.family()), 0);
+    assert_eq!(report.count(Opcode::LdILong.family()), 1);
+    assert_eq!(report.count(Opcode::Plane(0).family()), 1);
+    assert_eq!(report.families().count(), 2);
+    assert_eq!(report.unknown(), 0);
+    assert_eq!(report.required_mode(), Mode::XoChip);
+}
+
+#[test]
+fn plain_roms_need_chip8() {
+    let report = analyze_rom(&[0x60, 0x01, 0x12, 0x00]);
+    assert_eq!(report.required_mode(), Mode::Chip8);
+    assert_eq!(report.unknown(), 0);
+}
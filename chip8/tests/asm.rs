@@ -0,0 +1,101 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use chip8::asm::{assemble, assemble_program, AssembleError};
+use chip8::opcode::decode;
+use common::run;
+
+#[test]
+fn assembles_single_instructions() {
+    assert_eq!(assemble("LD V1, 5"), Ok(0x6105));
+    assert_eq!(assemble("drw v0, v1, 3"), Ok(0xD013));
+    assert_eq!(assemble("JMP 200"), Ok(0x1200));
+    assert_eq!(assemble("JP 0x2A0 ; jump"), Ok(0x12A0));
+    assert_eq!(assemble("LD [I], VA"), Ok(0xFA55));
+    assert_eq!(assemble("LD [I], V10"), Ok(0xFA55));
+    assert_eq!(assemble("LD V0, b"), Ok(0x600B));
+    assert_eq!(assemble("LD F, V2"), Ok(0xF229));
+}
+
+#[test]
+fn reassembles_disassembly() {
+    for instruction in 0..=u16::MAX {
+        if let Some(opcode) = decode(instruction) {
+            assert_eq!(assemble(&opcode.to_string()), Ok(instruction), "{}", opcode);
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "debug")]
+fn reassembles_jump_with_vx_disassembly() {
+    use chip8::chip8::Chip8;
+    use chip8::quirks::Quirks;
+    use common::FixedRandom;
+
+    for instruction in 0xB000..=0xBFFF {
+        let text = Chip8::<FixedRandom>::print_instruction(instruction, &Quirks::schip());
+        assert_eq!(assemble(&text), Ok(instruction), "{}", text);
+    }
+}
+
+#[test]
+fn jump_with_vx_register_matches_the_address() {
+    assert_eq!(assemble("JP V2, 234"), Ok(0xB234));
+    assert_eq!(assemble("JP V0, 234"), Ok(0xB234));
+    assert!(assemble("JP V3, 234").is_err());
+}
+
+#[test]
+fn rejects_bad_operands() {
+    assert!(assemble("LD V1, 100").is_err());
+    assert!(assemble("DRW V0, V1, 10").is_err());
+    assert!(assemble("JMP 1000").is_err());
+    assert!(assemble("OR V0, 5").is_err());
+    assert!(assemble("NOP").is_err());
+}
+
+#[test]
+fn assembles_programs_with_labels() {
+    let source = "
+        ; Draw a square and stop
+                LD I, square
+                DRW V0, V0, 2
+        end:    JMP end
+        square: DB F0, 0xF0
+    ";
+    let rom = assemble_program(source).unwrap();
+    assert_eq!(rom, [0xA2, 0x06, 0xD0, 0x02, 0x12, 0x04, 0xF0, 0xF0]);
+
+    let machine = run(&rom, 3);
+    assert!(machine.is_halted());
+    assert_eq!(machine.get_display()[..4], [1, 1, 1, 1]);
+}
+
+#[test]
+fn long_load_takes_an_address_word() {
+    let rom = assemble_program("LD I, LONG 1234\nLD I, LONG").unwrap();
+    assert_eq!(rom, [0xF0, 0x00, 0x12, 0x34, 0xF0, 0x00]);
+}
+
+#[test]
+fn errors_report_the_line() {
+    assert_eq!(
+        assemble_program("CLS\nJMP nowhere\n"),
+        Err(AssembleError {
+            line: 2,
+            message: "unknown label nowhere".to_string()
+        })
+    );
+
+    // A label at the very end of memory doesn't fit in a 12 bit address
+    let padding = "DB 0\n".repeat(0xDFE);
+    let error = assemble_program(&format!("JMP end\n{}end:\n", padding)).unwrap_err();
+    assert_eq!(error.line, 1);
+    assert_eq!(error.message, "label end at 1000 out of range");
+
+    let error = assemble_program("a: CLS\na: RET").unwrap_err();
+    assert_eq!(error.line, 2);
+    assert_eq!(error.to_string(), "line 2: duplicate label a");
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use chip8::chip8::{Chip8Builder, HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH};
+use chip8::error::Chip8Error;
+use chip8::quirks::Quirks;
+use common::FixedRandom;
+
+#[test]
+fn defaults_match_new() {
+    let machine = Chip8Builder::new(&[0x60, 0x01], FixedRandom(0))
+        .build()
+        .unwrap();
+
+    assert_eq!(machine.quirks(), &Quirks::default());
+    assert_eq!(machine.program_counter(), 0x200);
+    assert_eq!(machine.display_dimensions(), (64, 32));
+    // Top row of the built-in "0"
+    assert_eq!(machine.read_memory(0), 0xF0);
+}
+
+#[test]
+fn chained_configuration() {
+    let mut fontset = [0; 80];
+    fontset[5..10].copy_from_slice(&[0x18, 0x18, 0x18, 0x18, 0x18]);
+
+    // LD V0, 1; LD F, V0
+    let mut machine = Chip8Builder::new(&[0x60, 0x01, 0xF0, 0x29], FixedRandom(0))
+        .quirks(Quirks::schip())
+        .fontset(fontset)
+        .load_address(0x300)
+        .high_res(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(machine.quirks(), &Quirks::schip());
+    assert_eq!(machine.program_counter(), 0x300);
+    assert_eq!(
+        machine.display_dimensions(),
+        (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT)
+    );
+
+    machine.execute_instruction().unwrap();
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.index_register(), 5);
+    assert_eq!(machine.read_memory(5), 0x18);
+
+    // The custom fontset survives loading another ROM
+    machine.load_rom(&[]).unwrap();
+    assert_eq!(machine.read_memory(5), 0x18);
+}
+
+#[test]
+fn build_rejects_oversized_roms() {
+    let rom = [0; 0x100];
+    let result = Chip8Builder::new(&rom, FixedRandom(0))
+        .load_address(0xF80)
+        .build();
+    assert_eq!(
+        result.err(),
+        Some(Chip8Error::RomTooLarge {
+            size: 0x100,
+            max: 0x80
+        })
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn from_reader_loads_the_stream() {
+    use chip8::chip8::Chip8;
+    use std::io::Cursor;
+
+    // LD V0, 0x42
+    let mut reader = Cursor::new(vec![0x60, 0x42]);
+    let mut machine = Chip8::from_reader(&mut reader, FixedRandom(0)).unwrap();
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.registers()[0], 0x42);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn from_reader_rejects_oversized_streams() {
+    use chip8::chip8::Chip8;
+    use std::io::Cursor;
+
+    let mut reader = Cursor::new(vec![0; 0x2000]);
+    let result = Chip8::from_reader(&mut reader, FixedRandom(0));
+    assert_eq!(
+        result.err(),
+        Some(Chip8Error::RomTooLarge {
+            size: 0x2000,
+            max: 0xE00
+        })
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn read_rom_feeds_larger_memories() {
+    use chip8::chip8::{read_rom, MAX_MEMORY_SIZE};
+    use std::io::{Cursor, Read};
+
+    let mut reader = Cursor::new(vec![0; 0x2000]);
+    let rom = read_rom(&mut reader).unwrap();
+    let machine = Chip8Builder::new(&rom, FixedRandom(0))
+        .memory_size(MAX_MEMORY_SIZE)
+        .build()
+        .unwrap();
+    assert_eq!(machine.memory_size(), MAX_MEMORY_SIZE);
+
+    // Endless streams stop past the largest memory
+    let rom = read_rom(&mut std::io::repeat(0).take(0x20000)).unwrap();
+    assert_eq!(rom.len(), MAX_MEMORY_SIZE + 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn large_memory_is_reached_through_long_loads() {
+    use chip8::chip8::MAX_MEMORY_SIZE;
+
+    // LD I, LONG 0xFFFF; LD V1, 7; LD [I], V1 wraps around to 0
+    let rom = [0xF0, 0x00, 0xFF, 0xFF, 0x61, 0x07, 0xF1, 0x55];
+    let mut machine = Chip8Builder::new(&rom, FixedRandom(0))
+        .quirks(Quirks::xo_chip())
+        .memory_size(MAX_MEMORY_SIZE)
+        .build()
+        .unwrap();
+    assert_eq!(machine.memory_size(), MAX_MEMORY_SIZE);
+
+    for _ in 0..3 {
+        machine.execute_instruction().unwrap();
+    }
+    assert_eq!(machine.read_memory(0xFFFF), 0);
+    assert_eq!(machine.read_memory(0x0000), 7);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn memory_size_is_checked() {
+    for size in [0x800, 0x10001] {
+        let result = Chip8Builder::new(&[], FixedRandom(0))
+            .memory_size(size)
+            .build();
+        assert_eq!(result.err(), Some(Chip8Error::InvalidMemorySize { size }));
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn pc_wraps_at_the_end_of_large_memory() {
+    use chip8::chip8::{GAME_ROM_OFFSET, MAX_MEMORY_SIZE};
+
+    // LD V0, 0 up to the last word, SE V0, 0 at 0xFFFE skips past the end
+    let mut rom = [0x60, 0x00].repeat((MAX_MEMORY_SIZE - GAME_ROM_OFFSET) / 2);
+    let last = rom.len() - 2;
+    rom[last] = 0x30;
+    let mut machine = Chip8Builder::new(&rom, FixedRandom(0))
+        .memory_size(MAX_MEMORY_SIZE)
+        .build()
+        .unwrap();
+
+    machine.run_cycles(rom.len() / 2 - 1).unwrap();
+    assert_eq!(machine.program_counter(), 0xFFFE);
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.program_counter(), 0x0002);
+}
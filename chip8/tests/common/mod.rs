@@ -0,0 +1,60 @@
+use chip8::chip8::Chip8;
+use chip8::io::Random;
+use chip8::quirks::Quirks;
+
+/// Random source that always returns the same byte
+pub struct FixedRandom(pub u8);
+
+impl Random for FixedRandom {
+    fn randint(&mut self) -> u8 {
+        self.0
+    }
+}
+
+/// Load `rom` and execute its first `steps` instructions
+#[allow(dead_code)]
+pub fn run(rom: &[u8], steps: usize) -> Chip8<FixedRandom> {
+    run_with(rom, Quirks::default(), steps)
+}
+
+/// Like `run`, with `quirks` set before the first instruction
+#[allow(dead_code)]
+pub fn run_with(rom: &[u8], quirks: Quirks, steps: usize) -> Chip8<FixedRandom> {
+    let mut machine = Chip8::new(rom, FixedRandom(0)).unwrap().with_quirks(quirks);
+    for _ in 0..steps {
+        machine.execute_instruction().unwrap();
+    }
+    machine
+}
+
+/// Number of lit pixels in either resolution
+#[allow(dead_code)]
+pub fn lit_pixels(machine: &Chip8<FixedRandom>) -> usize {
+    machine.pixels().filter(|&(_, _, lit)| lit).count()
+}
+
+/// Trace writer the test keeps a handle to, see `Chip8::set_trace_writer`
+#[cfg(feature = "std")]
+#[allow(dead_code)]
+#[derive(Clone, Default)]
+pub struct SharedBuffer(pub std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+#[cfg(feature = "std")]
+impl std::io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+#[allow(dead_code)]
+impl SharedBuffer {
+    /// Everything written so far
+    pub fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
@@ -0,0 +1,92 @@
+; ALU, skip and BCD results drawn as hex bytes, five per row. Results
+; that set VF are followed by it.
+
+        LD V8, 2
+        LD V9, 1
+
+        LD V0, FF       ; 01 01
+        LD V1, 2
+        ADD V0, V1
+        CALL flagged
+
+        LD V0, 5        ; FE 00, VF is clear on a borrow
+        LD V1, 7
+        SUB V0, V1
+        CALL flagged
+
+        LD V0, 7        ; FE 00
+        LD V1, 5
+        SUBN V0, V1
+        CALL flagged
+
+        LD V1, 3        ; 01 01
+        SHR V0, V1
+        CALL flagged
+
+        LD V1, 81       ; 02 01
+        SHL V0, V1
+        CALL flagged
+
+        LD V0, F0       ; FF
+        LD V1, 0F
+        OR V0, V1
+        CALL value
+
+        LD V0, 3C       ; 14
+        LD V1, 57
+        AND V0, V1
+        CALL value
+
+        XOR V0, V1      ; 43
+        CALL value
+
+        LD V0, FE       ; 01, no carry for the byte form
+        ADD V0, 3
+        CALL value
+
+        LD V0, 0        ; 10, SE skips and SNE doesn't
+        SE V0, 0
+        ADD V0, 1
+        SNE V0, 0
+        ADD V0, 10
+        CALL value
+
+        LD V0, 9C       ; 01 05 06
+        LD I, scratch
+        LD B, V0
+        LD V2, [I]
+        LD VE, V2
+        CALL value
+        LD V0, V1
+        CALL value
+        LD V0, VE
+        CALL value
+
+end:    JP end
+
+; Show V0, then the VF it was computed with
+flagged: LD VD, VF
+        CALL value
+        LD V0, VD
+
+; Show V0 as two hex digits at V8, V9 and move on to the next slot
+value:  LD VB, V0
+        SHR VB, VB
+        SHR VB, VB
+        SHR VB, VB
+        SHR VB, VB
+        LD F, VB
+        DRW V8, V9, 5
+        ADD V8, 5
+        LD VB, F
+        AND VB, V0
+        LD F, VB
+        DRW V8, V9, 5
+        ADD V8, 7
+        SE V8, 3E
+        RET
+        LD V8, 2
+        ADD V9, 7
+        RET
+
+scratch: DB 0, 0, 0
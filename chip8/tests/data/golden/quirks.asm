@@ -0,0 +1,33 @@
+; Draws what depends on the quirks: the digit picked by JP V0 (1 normally,
+; 2 with jump_with_vx), a bar at the right edge that is clipped or wraps
+; around, and a DXY0 sprite that is 16x16 with lores_large_sprites and
+; empty otherwise.
+
+        LD V0, 0
+        LD V2, 2
+        JP V0, table
+table:  JP plain
+        JP vx
+plain:  LD VA, 1
+        JP shown
+vx:     LD VA, 2
+shown:  LD F, VA
+        LD V8, 2
+        LD V9, 2
+        DRW V8, V9, 5
+
+        LD V8, 3C
+        LD V9, 2
+        LD I, bar
+        DRW V8, V9, 4
+
+        LD V8, 18
+        LD V9, 8
+        LD I, block
+        DRW V8, V9, 0
+
+end:    JP end
+
+bar:    DB FF, 81, 81, FF
+block:  DB FF, FF, 80, 01, 80, 01, 80, 01, 80, 01, 80, 01, 80, 01, 80, 01
+        DB 80, 01, 80, 01, 80, 01, 80, 01, 80, 01, 80, 01, 80, 01, FF, FF
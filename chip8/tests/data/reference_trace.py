@@ -0,0 +1,139 @@
+#!/usr/bin/env python3
+"""Reference CHIP-8 interpreter that writes the trace in reference.trace
+
+Written from the instruction set description, independently of the Rust
+core, so the trace can catch the core getting an instruction wrong and not
+only changes to it. It follows Octo's default behaviour: 8XY6 and 8XYE shift
+VY into VX, FX55 and FX65 advance I, the logic ops leave VF alone and BNNN
+adds V0. Drawing, keys and timers aren't implemented, reference.ch8 doesn't
+use them.
+
+    python3 reference_trace.py reference.ch8 29 > reference.trace
+"""
+
+import sys
+
+LOAD_ADDRESS = 0x200
+MEMORY_SIZE = 0x1000
+
+
+class Machine:
+    def __init__(self, rom):
+        self.memory = bytearray(MEMORY_SIZE)
+        self.memory[LOAD_ADDRESS:LOAD_ADDRESS + len(rom)] = rom
+        self.v = [0] * 16
+        self.i = 0
+        self.pc = LOAD_ADDRESS
+        self.stack = []
+
+    def trace_line(self, opcode):
+        registers = " ".join("%02x" % value for value in self.v)
+        return "%04x %04x %04x %02x %s" % (
+            self.pc, opcode, self.i, len(self.stack), registers)
+
+    def step(self, out):
+        opcode = self.memory[self.pc] << 8 | self.memory[self.pc + 1]
+        out.write(self.trace_line(opcode) + "\n")
+        self.pc += 2
+
+        x = opcode >> 8 & 0xF
+        y = opcode >> 4 & 0xF
+        n = opcode & 0xF
+        nn = opcode & 0xFF
+        nnn = opcode & 0xFFF
+        v = self.v
+
+        if opcode == 0x00EE:
+            self.pc = self.stack.pop()
+        elif opcode >> 12 == 0x1:
+            self.pc = nnn
+        elif opcode >> 12 == 0x2:
+            self.stack.append(self.pc)
+            self.pc = nnn
+        elif opcode >> 12 == 0x3:
+            if v[x] == nn:
+                self.pc += 2
+        elif opcode >> 12 == 0x4:
+            if v[x] != nn:
+                self.pc += 2
+        elif opcode & 0xF00F == 0x5000:
+            if v[x] == v[y]:
+                self.pc += 2
+        elif opcode >> 12 == 0x6:
+            v[x] = nn
+        elif opcode >> 12 == 0x7:
+            v[x] = (v[x] + nn) & 0xFF
+        elif opcode >> 12 == 0x8:
+            self.arithmetic(x, y, n)
+        elif opcode & 0xF00F == 0x9000:
+            if v[x] != v[y]:
+                self.pc += 2
+        elif opcode >> 12 == 0xA:
+            self.i = nnn
+        elif opcode >> 12 == 0xB:
+            self.pc = nnn + v[0]
+        elif opcode & 0xF0FF == 0xF01E:
+            self.i = (self.i + v[x]) & 0xFFFF
+        elif opcode & 0xF0FF == 0xF033:
+            self.memory[self.i:self.i + 3] = bytes(
+                [v[x] // 100, v[x] // 10 % 10, v[x] % 10])
+        elif opcode & 0xF0FF == 0xF055:
+            for index in range(x + 1):
+                self.memory[self.i + index] = v[index]
+            self.i += x + 1
+        elif opcode & 0xF0FF == 0xF065:
+            for index in range(x + 1):
+                v[index] = self.memory[self.i + index]
+            self.i += x + 1
+        else:
+            raise ValueError("unsupported instruction %04x" % opcode)
+
+    def arithmetic(self, x, y, n):
+        v = self.v
+        if n == 0x0:
+            v[x] = v[y]
+        elif n == 0x1:
+            v[x] |= v[y]
+        elif n == 0x2:
+            v[x] &= v[y]
+        elif n == 0x3:
+            v[x] ^= v[y]
+        elif n == 0x4:
+            total = v[x] + v[y]
+            v[x] = total & 0xFF
+            v[0xF] = int(total > 0xFF)
+        elif n == 0x5:
+            borrow = v[y] > v[x]
+            v[x] = (v[x] - v[y]) & 0xFF
+            v[0xF] = int(not borrow)
+        elif n == 0x6:
+            bit = v[y] & 1
+            v[x] = v[y] >> 1
+            v[0xF] = bit
+        elif n == 0x7:
+            borrow = v[x] > v[y]
+            v[x] = (v[y] - v[x]) & 0xFF
+            v[0xF] = int(not borrow)
+        elif n == 0xE:
+            bit = v[y] >> 7
+            v[x] = v[y] << 1 & 0xFF
+            v[0xF] = bit
+        else:
+            raise ValueError("unsupported instruction 8%x%x%x" % (x, y, n))
+
+
+def main():
+    path, steps = sys.argv[1], int(sys.argv[2])
+    with open(path, "rb") as rom:
+        machine = Machine(rom.read())
+
+    out = sys.stdout
+    out.write("# Trace of reference.ch8 from reference_trace.py, an interpreter\n")
+    out.write("# written independently of the core, see the script\n")
+    out.write("# PC OPCODE I SP V0..VF, state before each instruction\n")
+    for _ in range(steps):
+        machine.step(out)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,27 @@
+#![cfg(feature = "debug")]
+
+mod common;
+
+#[test]
+fn debug_snapshot_names_the_state() {
+    // CALL 204, then LD V1, 5; LD DT, V1; LD ST, V1; RET
+    let rom = [
+        0x22, 0x04, 0x00, 0x00, 0x61, 0x05, 0xF1, 0x15, 0xF1, 0x18, 0x00, 0xEE,
+    ];
+    let machine = common::run(&rom, 4);
+    let snapshot = machine.debug_snapshot();
+
+    assert_eq!(snapshot.pc, 0x20A);
+    assert_eq!(snapshot.opcode, 0x00EE);
+    assert_eq!(snapshot.mnemonic, "RET");
+    assert_eq!(snapshot.registers[1], 5);
+    assert_eq!(snapshot.sp, 1);
+    assert_eq!(snapshot.stack[0], 0x202);
+    assert_eq!((snapshot.delay, snapshot.sound), (5, 5));
+
+    let (pc, instruction, v, i) = machine.get_debug_info();
+    assert_eq!(
+        (pc, instruction, v, i),
+        (0x20A, 0x00EE, snapshot.registers, 0)
+    );
+}
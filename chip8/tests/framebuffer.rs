@@ -0,0 +1,122 @@
+mod common;
+
+use chip8::chip8::{Chip8, SCREEN_HEIGHT, SCREEN_WIDTH};
+use chip8::error::Chip8Error;
+use common::FixedRandom;
+
+const PACKED_LEN: usize = SCREEN_WIDTH * SCREEN_HEIGHT / 8;
+
+#[test]
+fn render_packed_into_fills_buffer() {
+    // DRW V0, V0, 5 with I = 0 draws the "0" glyph at the top left corner
+    let mut machine = Chip8::new(&[0xD0, 0x05], FixedRandom(0)).unwrap();
+    machine.execute_instruction().unwrap();
+
+    let mut out = [0xAA; PACKED_LEN];
+    machine.render_packed_into(&mut out).unwrap();
+
+    let row_bytes = SCREEN_WIDTH / 8;
+    let mut expected = [0; PACKED_LEN];
+    for (row, glyph) in [0xF0, 0x90, 0x90, 0x90, 0xF0].iter().enumerate() {
+        expected[row * row_bytes] = *glyph;
+    }
+    assert_eq!(out[..], expected[..]);
+}
+
+#[test]
+fn render_packed_into_rejects_small_buffer() {
+    let machine = Chip8::new(&[], FixedRandom(0)).unwrap();
+
+    let mut out = [0; PACKED_LEN - 1];
+    assert_eq!(
+        machine.render_packed_into(&mut out),
+        Err(Chip8Error::BufferTooSmall {
+            required: PACKED_LEN,
+            actual: PACKED_LEN - 1,
+        })
+    );
+}
+
+#[test]
+fn get_display_packed_matches_get_display() {
+    // DRW V0, V0, 5 draws the "0" glyph
+    let mut machine = Chip8::new(&[0xD0, 0x05], FixedRandom(0)).unwrap();
+    machine.execute_instruction().unwrap();
+
+    let mut out = [0; PACKED_LEN];
+    machine.get_display_packed(&mut out).unwrap();
+    for (index, pixel) in machine.get_display().iter().enumerate() {
+        let bit = out[index / 8] >> (7 - index % 8) & 1;
+        assert_eq!(bit, (*pixel != 0) as u8, "pixel {}", index);
+    }
+
+    assert!(machine.get_display_packed(&mut out[1..]).is_err());
+}
+
+#[test]
+fn display_dirty_flag() {
+    // CLS, then JMP to self
+    let mut machine = Chip8::new(&[0x00, 0xE0, 0x12, 0x02], FixedRandom(0)).unwrap();
+
+    // The first frame always draws
+    assert!(machine.take_display_dirty());
+    assert!(!machine.take_display_dirty());
+
+    machine.execute_instruction().unwrap();
+    assert!(machine.take_display_dirty());
+
+    machine.execute_instruction().unwrap();
+    assert!(!machine.take_display_dirty());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn to_svg_renders_lit_pixels() {
+    // DRW V0, V0, 5 draws the "0" glyph, 14 pixels are lit
+    let mut machine = Chip8::new(&[0xD0, 0x05], FixedRandom(0)).unwrap();
+    machine.execute_instruction().unwrap();
+
+    let svg = machine.to_svg(10);
+
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>"));
+    assert!(svg.contains("width=\"640\" height=\"320\""));
+    // background plus one rect per lit pixel
+    assert_eq!(svg.matches("<rect").count(), 1 + 14);
+    assert!(svg.contains("<rect x=\"30\" y=\"10\" width=\"10\" height=\"10\""));
+}
+
+#[test]
+fn packed_framebuffer_round_trip() {
+    // LD V0, 3A; LD V1, 0D; LD F, V1; DRW V0, V1, 5; CLS
+    let rom = [0x60, 0x3A, 0x61, 0x0D, 0xF1, 0x29, 0xD0, 0x15, 0x00, 0xE0];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+    for _ in 0..4 {
+        machine.execute_instruction().unwrap();
+    }
+    let original = machine.get_display().to_vec();
+
+    let mut packed = [0; PACKED_LEN];
+    machine.render_packed_into(&mut packed).unwrap();
+
+    machine.execute_instruction().unwrap();
+    assert!(machine.get_display().iter().all(|&p| p == 0));
+
+    machine.take_display_dirty();
+    machine.load_packed_framebuffer(&packed).unwrap();
+    assert_eq!(machine.get_display(), &original[..]);
+    assert!(machine.take_display_dirty());
+}
+
+#[test]
+fn load_packed_framebuffer_checks_length() {
+    let mut machine = Chip8::new(&[], FixedRandom(0)).unwrap();
+
+    assert_eq!(
+        machine.load_packed_framebuffer(&[0; PACKED_LEN + 1]),
+        Err(Chip8Error::BufferLengthMismatch {
+            expected: PACKED_LEN,
+            actual: PACKED_LEN + 1,
+        })
+    );
+}
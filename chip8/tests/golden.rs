@@ -0,0 +1,121 @@
+//! Runs small test ROMs and compares the screen with a known good one
+//!
+//! The ROMs are assembled from the `.asm` files next to them in
+//! `data/golden`, the `.golden` files hold the packed framebuffer.
+
+use chip8::chip8::Chip8;
+use chip8::io::SeededRandom;
+use chip8::quirks::Quirks;
+
+/// Every test ROM ends in a self-jump well before this
+const MAX_CYCLES: usize = 10_000;
+
+const OPCODES: &[u8] = include_bytes!("data/golden/opcodes.ch8");
+const QUIRKS: &[u8] = include_bytes!("data/golden/quirks.ch8");
+
+/// Run `rom` until it halts and return the packed framebuffer
+fn run_packed(rom: &[u8], quirks: Quirks) -> Vec<u8> {
+    let mut machine = Chip8::new(rom, SeededRandom::new(1))
+        .unwrap()
+        .with_quirks(quirks);
+    machine.run_cycles(MAX_CYCLES).unwrap();
+    assert!(machine.is_halted(), "ROM didn't halt");
+
+    let mut packed = vec![0; machine.get_display().len() / 8];
+    machine.get_display_packed(&mut packed).unwrap();
+    packed
+}
+
+/// Expected and actual screen side by side, differing pixels marked below
+fn ascii_diff(expected: &[u8], actual: &[u8], width: usize) -> String {
+    let pixel = |packed: &[u8], index: usize| packed[index / 8] & (0x80 >> (index % 8)) != 0;
+    let row_bytes = width / 8;
+
+    let mut out = format!("{:<width$}   {}\n", "expected", "actual", width = width);
+    for row in 0..expected.len() / row_bytes {
+        let mut marks = String::new();
+        for (packed, separator) in [(expected, "   "), (actual, "\n")] {
+            for column in 0..width {
+                out.push(if pixel(packed, row * width + column) {
+                    '#'
+                } else {
+                    '.'
+                });
+            }
+            out.push_str(separator);
+        }
+        for column in 0..width {
+            let index = row * width + column;
+            marks.push(if pixel(expected, index) != pixel(actual, index) {
+                '^'
+            } else {
+                ' '
+            });
+        }
+        if marks.contains('^') {
+            out.push_str(&marks);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn check_golden(rom: &[u8], quirks: Quirks, golden: &[u8]) {
+    let actual = run_packed(rom, quirks);
+    assert_eq!(actual.len(), golden.len(), "resolution changed");
+    if actual != golden {
+        panic!(
+            "framebuffer differs from the golden one\n{}",
+            ascii_diff(golden, &actual, 64)
+        );
+    }
+}
+
+#[test]
+fn opcodes() {
+    check_golden(
+        OPCODES,
+        Quirks::cosmac_vip(),
+        include_bytes!("data/golden/opcodes.golden"),
+    );
+}
+
+#[test]
+fn quirks_cosmac_vip() {
+    check_golden(
+        QUIRKS,
+        Quirks::cosmac_vip(),
+        include_bytes!("data/golden/quirks_vip.golden"),
+    );
+}
+
+#[test]
+fn quirks_schip() {
+    check_golden(
+        QUIRKS,
+        Quirks::schip(),
+        include_bytes!("data/golden/quirks_schip.golden"),
+    );
+}
+
+#[test]
+fn quirks_xo_chip() {
+    check_golden(
+        QUIRKS,
+        Quirks::xo_chip(),
+        include_bytes!("data/golden/quirks_xo_chip.golden"),
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn roms_match_their_source() {
+    use chip8::asm::assemble_program;
+
+    for (source, rom) in [
+        (include_str!("data/golden/opcodes.asm"), OPCODES),
+        (include_str!("data/golden/quirks.asm"), QUIRKS),
+    ] {
+        assert_eq!(assemble_program(source).unwrap(), rom);
+    }
+}
@@ -0,0 +1,250 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn add_sets_carry() {
+    // LD V0, 0xF0; LD V1, 0x20; ADD V0, V1
+    let machine = run(&[0x60, 0xF0, 0x61, 0x20, 0x80, 0x14], 3);
+    assert_eq!(machine.registers()[0], 0x10);
+    assert_eq!(machine.registers()[0xF], 1);
+
+    // LD V0, 0x10; LD V1, 0x20; ADD V0, V1
+    let machine = run(&[0x60, 0x10, 0x61, 0x20, 0x80, 0x14], 3);
+    assert_eq!(machine.registers()[0], 0x30);
+    assert_eq!(machine.registers()[0xF], 0);
+}
+
+#[test]
+fn add_byte_wraps_without_carry() {
+    // LD VF, 0x05; LD V0, 0xFF; ADD V0, 0x02
+    let machine = run(&[0x6F, 0x05, 0x60, 0xFF, 0x70, 0x02], 3);
+    assert_eq!(machine.registers()[0], 0x01);
+    assert_eq!(machine.registers()[0xF], 0x05);
+}
+
+#[test]
+fn bitwise_operations() {
+    // LD V0, 0b1100; LD V1, 0b1010; LD V2, 0b1100; LD V3, 0b1100
+    // OR V0, V1; AND V2, V1; XOR V3, V1
+    let rom = [
+        0x60, 0x0C, 0x61, 0x0A, 0x62, 0x0C, 0x63, 0x0C, 0x80, 0x11, 0x82, 0x12, 0x83, 0x13,
+    ];
+    let machine = run(&rom, 7);
+    assert_eq!(machine.registers()[0], 0b1110);
+    assert_eq!(machine.registers()[2], 0b1000);
+    assert_eq!(machine.registers()[3], 0b0110);
+}
+
+#[test]
+fn sub_results() {
+    // LD V0, 0x30; LD V1, 0x10; SUB V0, V1
+    let machine = run(&[0x60, 0x30, 0x61, 0x10, 0x80, 0x15], 3);
+    assert_eq!(machine.registers()[0], 0x20);
+
+    // LD V0, 0x10; LD V1, 0x30; SUBN V0, V1
+    let machine = run(&[0x60, 0x10, 0x61, 0x30, 0x80, 0x17], 3);
+    assert_eq!(machine.registers()[0], 0x20);
+}
+
+#[test]
+fn sub_sets_vf_when_there_is_no_borrow() {
+    // LD V0, 0x30; LD V1, 0x10; SUB V0, V1
+    let machine = run(&[0x60, 0x30, 0x61, 0x10, 0x80, 0x15], 3);
+    assert_eq!(machine.registers()[0xF], 1);
+
+    // Equal values don't borrow either
+    // LD V0, 0x30; LD V1, 0x30; SUB V0, V1
+    let machine = run(&[0x60, 0x30, 0x61, 0x30, 0x80, 0x15], 3);
+    assert_eq!(machine.registers()[0], 0);
+    assert_eq!(machine.registers()[0xF], 1);
+
+    // LD V0, 0x10; LD V1, 0x30; SUB V0, V1
+    let machine = run(&[0x60, 0x10, 0x61, 0x30, 0x80, 0x15], 3);
+    assert_eq!(machine.registers()[0], 0xE0);
+    assert_eq!(machine.registers()[0xF], 0);
+}
+
+#[test]
+fn subn_sets_vf_when_there_is_no_borrow() {
+    // LD V0, 0x10; LD V1, 0x30; SUBN V0, V1
+    let machine = run(&[0x60, 0x10, 0x61, 0x30, 0x80, 0x17], 3);
+    assert_eq!(machine.registers()[0xF], 1);
+
+    // LD V0, 0x30; LD V1, 0x30; SUBN V0, V1
+    let machine = run(&[0x60, 0x30, 0x61, 0x30, 0x80, 0x17], 3);
+    assert_eq!(machine.registers()[0], 0);
+    assert_eq!(machine.registers()[0xF], 1);
+
+    // LD V0, 0x30; LD V1, 0x10; SUBN V0, V1
+    let machine = run(&[0x60, 0x30, 0x61, 0x10, 0x80, 0x17], 3);
+    assert_eq!(machine.registers()[0], 0xE0);
+    assert_eq!(machine.registers()[0xF], 0);
+}
+
+#[test]
+fn shifts_move_bit_into_vf() {
+    // LD V1, 0x81; SHR V0, V1
+    let machine = run(&[0x61, 0x81, 0x80, 0x16], 2);
+    assert_eq!(machine.registers()[0], 0x40);
+    assert_eq!(machine.registers()[0xF], 1);
+
+    // LD V1, 0x81; SHL V0, V1
+    let machine = run(&[0x61, 0x81, 0x80, 0x1E], 2);
+    assert_eq!(machine.registers()[0], 0x02);
+    assert_eq!(machine.registers()[0xF], 1);
+}
+
+#[test]
+fn skips() {
+    // LD V0, 0x12; SE V0, 0x12
+    let machine = run(&[0x60, 0x12, 0x30, 0x12], 2);
+    assert_eq!(machine.program_counter(), 0x206);
+
+    // LD V0, 0x12; SE V0, 0x13
+    let machine = run(&[0x60, 0x12, 0x30, 0x13], 2);
+    assert_eq!(machine.program_counter(), 0x204);
+
+    // LD V0, 0x12; SNE V0, 0x13
+    let machine = run(&[0x60, 0x12, 0x40, 0x13], 2);
+    assert_eq!(machine.program_counter(), 0x206);
+
+    // LD V0, 0x12; LD V1, 0x12; SE V0, V1
+    let machine = run(&[0x60, 0x12, 0x61, 0x12, 0x50, 0x10], 3);
+    assert_eq!(machine.program_counter(), 0x208);
+
+    // LD V0, 0x12; LD V1, 0x12; SNE V0, V1
+    let machine = run(&[0x60, 0x12, 0x61, 0x12, 0x90, 0x10], 3);
+    assert_eq!(machine.program_counter(), 0x206);
+}
+
+#[test]
+fn drw_reports_collisions() {
+    // LD F, V0 points I at the "0" glyph, drawn at (0, 0)
+    let rom = [0xF0, 0x29, 0xD0, 0x05, 0xD0, 0x05];
+
+    let machine = run(&rom, 2);
+    assert_eq!(machine.registers()[0xF], 0);
+    // Top row of "0" is 0xF0
+    assert_eq!(machine.get_display()[..8], [1, 1, 1, 1, 0, 0, 0, 0]);
+
+    // Drawing it again erases every pixel
+    let machine = run(&rom, 3);
+    assert_eq!(machine.registers()[0xF], 1);
+    assert!(machine.get_display().iter().all(|&pixel| pixel == 0));
+}
+
+#[test]
+fn ld_f_uses_the_low_nibble() {
+    // LD V0, 0x1A; LD F, V0
+    let machine = run(&[0x60, 0x1A, 0xF0, 0x29], 2);
+    assert_eq!(machine.index_register(), 0xA * 5);
+
+    // LD V0, 0xFF; LD F, V0 would overflow a u8 multiply
+    let machine = run(&[0x60, 0xFF, 0xF0, 0x29], 2);
+    assert_eq!(machine.index_register(), 0xF * 5);
+}
+
+/// `code` followed by the sprite row 0xF0 at 0x210
+fn with_sprite(code: &[u8]) -> Vec<u8> {
+    let mut rom = code.to_vec();
+    rom.resize(0x10, 0);
+    rom.push(0xF0);
+    rom
+}
+
+#[test]
+fn drw_collision_follows_each_draw() {
+    // LD I, 0x210; DRW V0, V0, 1; LD V1, 2; DRW V1, V0, 1; LD V2, 10; DRW V2, V0, 1
+    let rom = with_sprite(&[
+        0xA2, 0x10, 0xD0, 0x01, 0x61, 0x02, 0xD1, 0x01, 0x62, 0x0A, 0xD2, 0x01,
+    ]);
+
+    let machine = run(&rom, 2);
+    assert_eq!(machine.registers()[0xF], 0);
+
+    // The overlapping pixels 2 and 3 are erased
+    let machine = run(&rom, 4);
+    assert_eq!(machine.registers()[0xF], 1);
+    assert_eq!(machine.get_display()[..8], [1, 1, 0, 0, 1, 1, 0, 0]);
+
+    // A draw over unlit pixels clears the flag again
+    let machine = run(&rom, 6);
+    assert_eq!(machine.registers()[0xF], 0);
+    assert_eq!(machine.get_display()[8..16], [0, 0, 1, 1, 1, 1, 0, 0]);
+}
+
+#[test]
+fn drw_reads_vf_coordinate_before_setting_it() {
+    // LD VF, 8; LD I, 0x210; DRW VF, V0, 1
+    let rom = with_sprite(&[0x6F, 0x08, 0xA2, 0x10, 0xDF, 0x01]);
+
+    let machine = run(&rom, 3);
+    assert_eq!(
+        machine.get_display()[..12],
+        [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1]
+    );
+    assert_eq!(machine.registers()[0xF], 0);
+}
+
+#[test]
+fn write_protect_rom_catches_stores_into_the_program() {
+    use chip8::chip8::Chip8;
+    use chip8::error::Chip8Error;
+    use common::FixedRandom;
+
+    // LD I, 206; LD [I], V1; LD B, V0
+    let rom = [0xA2, 0x06, 0xF1, 0x55, 0xF0, 0x33, 0x00, 0x00];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+    machine.set_write_protect_rom(true);
+    machine.execute_instruction().unwrap();
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::RomWrite { addr: 0x206 })
+    );
+    assert_eq!(machine.read_memory(0x206), 0);
+
+    // The BCD digits would land on 206 to 208 as well
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::RomWrite { addr: 0x206 })
+    );
+}
+
+#[test]
+fn write_protect_rom_allows_stores_past_the_program() {
+    use chip8::chip8::Chip8;
+    use common::FixedRandom;
+
+    // LD I, 204; LD [I], V1
+    let rom = [0xA2, 0x04, 0xF1, 0x55];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+    machine.set_write_protect_rom(true);
+    machine.set_register(1, 7);
+    machine.execute_instruction().unwrap();
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.read_memory(0x205), 7);
+}
+
+#[test]
+fn strict_alignment_rejects_odd_pc() {
+    use chip8::chip8::Chip8;
+    use chip8::error::Chip8Error;
+    use common::FixedRandom;
+
+    // JMP 203, then LD V0, 1 at the odd address
+    let rom = [0x12, 0x03, 0x00, 0x60, 0x01];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+    machine.set_strict_alignment(true);
+    machine.execute_instruction().unwrap();
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::MisalignedPc { pc: 0x203 })
+    );
+    assert_eq!(machine.program_counter(), 0x203);
+
+    // Off by default, the odd address runs
+    machine.set_strict_alignment(false);
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.registers()[0], 1);
+}
@@ -0,0 +1,83 @@
+use chip8::opcode::{decode, Opcode};
+
+#[test]
+fn decodes_every_family() {
+    let cases = [
+        (0x00E0, Opcode::Cls),
+        (0x00EE, Opcode::Ret),
+        (0x1234, Opcode::Jmp(0x234)),
+        (0x2456, Opcode::Call(0x456)),
+        (0x3A12, Opcode::SeVxByte { x: 0xA, byte: 0x12 }),
+        (0x4B34, Opcode::SneVxByte { x: 0xB, byte: 0x34 }),
+        (0x5120, Opcode::SeVxVy { x: 1, y: 2 }),
+        (0x63FF, Opcode::LdVxByte { x: 3, byte: 0xFF }),
+        (0x7401, Opcode::AddVxByte { x: 4, byte: 0x01 }),
+        (0x8120, Opcode::LdVxVy { x: 1, y: 2 }),
+        (0x8121, Opcode::Or { x: 1, y: 2 }),
+        (0x8122, Opcode::And { x: 1, y: 2 }),
+        (0x8123, Opcode::Xor { x: 1, y: 2 }),
+        (0x8124, Opcode::AddVxVy { x: 1, y: 2 }),
+        (0x8125, Opcode::Sub { x: 1, y: 2 }),
+        (0x8126, Opcode::Shr { x: 1, y: 2 }),
+        (0x8127, Opcode::Subn { x: 1, y: 2 }),
+        (0x812E, Opcode::Shl { x: 1, y: 2 }),
+        (0x9120, Opcode::SneVxVy { x: 1, y: 2 }),
+        (0xA300, Opcode::LdI(0x300)),
+        (0xB400, Opcode::JpV0(0x400)),
+        (0xC50F, Opcode::Rnd { x: 5, byte: 0x0F }),
+        (0xD125, Opcode::Drw { x: 1, y: 2, n: 5 }),
+        (0xE69E, Opcode::Skp { x: 6 }),
+        (0xE7A1, Opcode::Sknp { x: 7 }),
+        (0xF807, Opcode::LdVxDt { x: 8 }),
+        (0xF90A, Opcode::LdVxK { x: 9 }),
+        (0xFA15, Opcode::LdDtVx { x: 0xA }),
+        (0xFB18, Opcode::LdStVx { x: 0xB }),
+        (0xFC1E, Opcode::AddIVx { x: 0xC }),
+        (0xFD29, Opcode::LdFVx { x: 0xD }),
+        (0xFE33, Opcode::LdBVx { x: 0xE }),
+        (0xFF55, Opcode::LdIVx { x: 0xF }),
+        (0xF065, Opcode::LdVxI { x: 0 }),
+        (0xF000, Opcode::LdILong),
+        (0xF201, Opcode::Plane(2)),
+        (0xF002, Opcode::Audio),
+        (0xF43A, Opcode::Pitch { x: 4 }),
+    ];
+
+    for (instruction, opcode) in cases.iter() {
+        assert_eq!(decode(*instruction), Some(*opcode), "{:04x}", instruction);
+    }
+}
+
+#[test]
+fn patterns_match_families() {
+    for instruction in 0..=u16::MAX {
+        if let Some(opcode) = decode(instruction) {
+            let digits = format!("{:04X}", instruction);
+            for (p, d) in opcode.pattern().chars().zip(digits.chars()) {
+                // lowercase letters are operands
+                assert!(
+                    p.is_lowercase() || p == d,
+                    "{} {}",
+                    opcode.pattern(),
+                    digits
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn rejects_invalid_instructions() {
+    for instruction in [0x0000, 0x00E1, 0x5121, 0x8128, 0x9121, 0xE000, 0xF0FF].iter() {
+        assert_eq!(decode(*instruction), None, "{:04x}", instruction);
+    }
+}
+
+#[test]
+fn encode_round_trips() {
+    for instruction in 0..=u16::MAX {
+        if let Some(opcode) = decode(instruction) {
+            assert_eq!(opcode.encode(), instruction, "{}", opcode);
+        }
+    }
+}
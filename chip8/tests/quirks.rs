@@ -0,0 +1,186 @@
+mod common;
+
+use chip8::chip8::Chip8;
+use chip8::quirks::{QuirkWarning, Quirks};
+use common::{lit_pixels, run_with, FixedRandom};
+
+/// LD V0, 1; LD V2, 10; JP V0, 234
+const JUMP_ROM: &[u8] = &[0x60, 0x01, 0x62, 0x10, 0xB2, 0x34];
+
+#[test]
+fn jump_offset_quirk() {
+    assert_eq!(
+        run_with(JUMP_ROM, Quirks::cosmac_vip(), 3).program_counter(),
+        0x235
+    );
+    assert_eq!(
+        run_with(JUMP_ROM, Quirks::schip(), 3).program_counter(),
+        0x244
+    );
+}
+
+#[test]
+#[cfg(feature = "debug")]
+fn print_instruction_shows_jump_form() {
+    assert_eq!(
+        Chip8::<FixedRandom>::print_instruction(0xB234, &Quirks::cosmac_vip()),
+        "JP V0, 234"
+    );
+    assert_eq!(
+        Chip8::<FixedRandom>::print_instruction(0xB234, &Quirks::schip()),
+        "JP V2, 234"
+    );
+}
+
+/// LD V0, 62; LD V1, 30; DRW V0, V1, 5 with I = 0 draws the "0" glyph
+const EDGE_SPRITE_ROM: &[u8] = &[0x60, 0x3E, 0x61, 0x1E, 0xD0, 0x15];
+
+#[test]
+fn sprites_clip_at_edges() {
+    let machine = run_with(EDGE_SPRITE_ROM, Quirks::default(), 3);
+
+    assert_eq!(lit_pixels(&machine), 3);
+    assert!(machine.get_pixel(62, 30));
+    assert!(machine.get_pixel(63, 30));
+    assert!(machine.get_pixel(62, 31));
+    assert!(!machine.get_pixel(0, 30));
+    assert!(!machine.get_pixel(62, 0));
+
+    // Only the on-screen pixels collide when drawing again
+    let mut redraw = Chip8::new(&[0xD0, 0x15, 0xD0, 0x15], FixedRandom(0)).unwrap();
+    redraw.set_register(0, 62);
+    redraw.set_register(1, 30);
+    redraw.execute_instruction().unwrap();
+    assert_eq!(redraw.registers()[0xF], 0);
+    redraw.execute_instruction().unwrap();
+    assert_eq!(redraw.registers()[0xF], 1);
+    assert_eq!(lit_pixels(&redraw), 0);
+}
+
+#[test]
+fn sprites_wrap_at_edges() {
+    let quirks = Quirks {
+        wrap_sprites: true,
+        ..Quirks::default()
+    };
+    let machine = run_with(EDGE_SPRITE_ROM, quirks, 3);
+
+    assert_eq!(lit_pixels(&machine), 14);
+    assert!(machine.get_pixel(62, 30));
+    assert!(machine.get_pixel(0, 30));
+    assert!(machine.get_pixel(1, 31));
+    assert!(machine.get_pixel(62, 0));
+    assert!(machine.get_pixel(1, 2));
+}
+
+/// LD V0, 1; ADD I, V0; SE VF, 1
+const VF_AFTER_ADD_I_ROM: &[u8] = &[0x60, 0x01, 0xF0, 0x1E, 0x3F, 0x01];
+
+#[test]
+fn vf_read_after_add_i_warns() {
+    let machine = run_with(VF_AFTER_ADD_I_ROM, Quirks::default(), 3);
+    assert_eq!(
+        machine.quirk_warnings().collect::<Vec<_>>(),
+        vec![QuirkWarning::AddISetsVf]
+    );
+}
+
+#[test]
+fn no_warning_when_quirk_enabled() {
+    let quirks = Quirks {
+        add_i_sets_vf: true,
+        ..Quirks::default()
+    };
+    assert!(run_with(VF_AFTER_ADD_I_ROM, quirks, 3)
+        .quirk_warnings()
+        .next()
+        .is_none());
+}
+
+#[test]
+fn no_warning_without_vf_read() {
+    // LD V0, 1; ADD I, V0; SE V1, 1
+    let rom = [0x60, 0x01, 0xF0, 0x1E, 0x31, 0x01];
+    assert!(run_with(&rom, Quirks::default(), 3)
+        .quirk_warnings()
+        .next()
+        .is_none());
+}
+
+/// LD VF, 0x42; LD V0, 5; LD I, 0xFFE; ADD I, V0
+const ADD_I_PAST_0FFF_ROM: &[u8] = &[0x6F, 0x42, 0x60, 0x05, 0xAF, 0xFE, 0xF0, 0x1E];
+
+#[test]
+fn add_i_leaves_vf_alone_by_default() {
+    let machine = run_with(ADD_I_PAST_0FFF_ROM, Quirks::default(), 4);
+    // I is not masked to 12 bits
+    assert_eq!(machine.index_register(), 0x1003);
+    assert_eq!(machine.registers()[0xF], 0x42);
+}
+
+#[test]
+fn add_i_sets_vf_past_0fff() {
+    let quirks = Quirks {
+        add_i_sets_vf: true,
+        ..Quirks::default()
+    };
+    let machine = run_with(ADD_I_PAST_0FFF_ROM, quirks, 4);
+    assert_eq!(machine.index_register(), 0x1003);
+    assert_eq!(machine.registers()[0xF], 1);
+
+    // LD VF, 0x42; LD V0, 1; LD I, 0xFFE; ADD I, V0
+    let machine = run_with(&[0x6F, 0x42, 0x60, 0x01, 0xAF, 0xFE, 0xF0, 0x1E], quirks, 4);
+    assert_eq!(machine.index_register(), 0x0FFF);
+    assert_eq!(machine.registers()[0xF], 0);
+}
+
+#[test]
+fn set_quirks_applies_to_later_instructions() {
+    // LD V0, 1; LD V2, 10; JP V0, 234
+    let mut machine = Chip8::new(JUMP_ROM, FixedRandom(0)).unwrap();
+    for _ in 0..2 {
+        machine.execute_instruction().unwrap();
+    }
+
+    machine.set_quirks(Quirks::chip48());
+    assert_eq!(machine.quirks(), &Quirks::chip48());
+    assert_eq!(machine.registers()[..3], [1, 0, 0x10]);
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.program_counter(), 0x244);
+}
+
+#[test]
+fn presets_are_named() {
+    use chip8::quirks::PRESETS;
+
+    let names: Vec<&str> = PRESETS.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, ["vip", "chip48", "schip", "xo-chip"]);
+    assert_eq!(PRESETS[0].1, Quirks::default());
+}
+
+/// LD V0, 1; DRW V0, V0, 1; ADD V0, 1; DRW V0, V0, 1; JP 208
+const DRAW_TWICE_ROM: &[u8] = &[0x60, 0x01, 0xD0, 0x01, 0x70, 0x01, 0xD0, 0x01, 0x12, 0x08];
+
+#[test]
+fn display_wait_ends_the_frame_after_drw() {
+    let quirks = Quirks {
+        display_wait: true,
+        ..Quirks::default()
+    };
+    let mut machine = run_with(DRAW_TWICE_ROM, quirks, 0);
+
+    assert_eq!(machine.run_cycles(10), Ok(2));
+    assert_eq!(machine.program_counter(), 0x204);
+
+    // One draw per frame
+    let frame = machine.advance(10).unwrap();
+    assert_eq!(frame.executed, 2);
+    assert_eq!(machine.program_counter(), 0x208);
+}
+
+#[test]
+fn drw_runs_through_without_display_wait() {
+    let mut machine = Chip8::new(DRAW_TWICE_ROM, FixedRandom(0)).unwrap();
+    assert_eq!(machine.run_cycles(4), Ok(4));
+    assert_eq!(machine.program_counter(), 0x208);
+}
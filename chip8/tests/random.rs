@@ -0,0 +1,20 @@
+use chip8::io::{Random, SeededRandom};
+
+fn bytes(seed: u64) -> Vec<u8> {
+    let mut rand = SeededRandom::new(seed);
+    (0..64).map(|_| rand.randint()).collect()
+}
+
+#[test]
+fn same_seed_same_stream() {
+    assert_eq!(bytes(42), bytes(42));
+    assert_ne!(bytes(42), bytes(43));
+}
+
+#[test]
+fn every_seed_produces_varied_bytes() {
+    for seed in [0, 1, 0x9E37_79B9_7F4A_7C15, u64::MAX] {
+        let stream = bytes(seed);
+        assert!(stream.iter().any(|&byte| byte != stream[0]));
+    }
+}
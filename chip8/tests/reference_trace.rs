@@ -0,0 +1,43 @@
+//! Runs `data/reference.ch8` with the trace writer on and compares the output
+//! with `data/reference.trace`
+//!
+//! The trace comes from `data/reference_trace.py`, a small interpreter
+//! written independently of the core, not from the core itself.
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip8::chip8::Chip8;
+use common::{FixedRandom, SharedBuffer};
+
+const ROM: &[u8] = include_bytes!("data/reference.ch8");
+const TRACE: &str = include_str!("data/reference.trace");
+
+#[test]
+fn matches_reference_trace() {
+    let expected: Vec<&str> = TRACE
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    let mut machine = Chip8::new(ROM, FixedRandom(0)).unwrap();
+    let buffer = SharedBuffer::default();
+    machine.set_trace_writer(buffer.clone());
+    for (step, expected) in expected.iter().enumerate() {
+        // An error is reported like any other divergence, with the step
+        let actual = match machine.execute_instruction() {
+            Ok(()) => buffer
+                .text()
+                .lines()
+                .nth(step)
+                .unwrap_or_default()
+                .to_string(),
+            Err(e) => format!("error: {}", e),
+        };
+        assert_eq!(
+            actual, *expected,
+            "diverged from the reference trace at step {}\n  expected: {}\n  actual:   {}",
+            step, expected, actual
+        );
+    }
+}
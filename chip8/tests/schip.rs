@@ -0,0 +1,93 @@
+mod common;
+
+use chip8::chip8::{Chip8, HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH, SCREEN_HEIGHT, SCREEN_WIDTH};
+use chip8::error::Chip8Error;
+use chip8::quirks::Quirks;
+use common::{lit_pixels, run_with, FixedRandom};
+
+/// 16x16 sprite: a frame with an empty inside
+const LARGE_SPRITE: [u8; 32] = [
+    0xFF, 0xFF, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01,
+    0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0xFF, 0xFF,
+];
+
+/// `setup` followed by LD I, 0x210; DRW V0, V1, 0, with the sprite at 0x210
+fn large_sprite_rom(setup: [u8; 2]) -> Vec<u8> {
+    let mut rom = vec![
+        setup[0], setup[1], 0x60, 0x04, 0x61, 0x02, 0xA2, 0x10, 0xD0, 0x10,
+    ];
+    rom.resize(0x10, 0);
+    rom.extend_from_slice(&LARGE_SPRITE);
+    rom
+}
+
+#[test]
+fn resolution_switch() {
+    // HIGH; LOW
+    let mut machine = run_with(&[0x00, 0xFF, 0x00, 0xFE], Quirks::schip(), 0);
+
+    machine.execute_instruction().unwrap();
+    assert_eq!(
+        machine.display_dimensions(),
+        (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT)
+    );
+    assert_eq!(
+        machine.get_display().len(),
+        HIRES_SCREEN_WIDTH * HIRES_SCREEN_HEIGHT
+    );
+
+    machine.execute_instruction().unwrap();
+    assert_eq!(machine.display_dimensions(), (SCREEN_WIDTH, SCREEN_HEIGHT));
+}
+
+#[test]
+fn resolution_opcodes_need_schip() {
+    let mut machine = Chip8::new(&[0x00, 0xFF], FixedRandom(0)).unwrap();
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::InvalidInstruction(0x00FF))
+    );
+}
+
+#[test]
+fn hires_draws_16x16_sprites() {
+    // HIGH, then draw at (4, 2)
+    let rom = large_sprite_rom([0x00, 0xFF]);
+    let machine = run_with(&rom, Quirks::schip(), 5);
+
+    assert_eq!(lit_pixels(&machine), 60);
+    for i in 0..16 {
+        // Top and bottom edges
+        assert!(machine.get_pixel(4 + i, 2));
+        assert!(machine.get_pixel(4 + i, 17));
+        // Left and right edges
+        assert!(machine.get_pixel(4, 2 + i));
+        assert!(machine.get_pixel(19, 2 + i));
+    }
+    assert!(!machine.get_pixel(5, 3));
+    assert_eq!(machine.registers()[0xF], 0);
+
+    // Drawing again erases the sprite and reports the collision
+    let mut rom = rom;
+    rom[10..12].copy_from_slice(&[0xD0, 0x10]);
+    let machine = run_with(&rom, Quirks::schip(), 6);
+    assert_eq!(machine.registers()[0xF], 1);
+    assert_eq!(lit_pixels(&machine), 0);
+}
+
+#[test]
+fn lores_large_sprites_follow_quirk() {
+    // LD V2, 0 in place of HIGH
+    let rom = large_sprite_rom([0x62, 0x00]);
+
+    let machine = run_with(&rom, Quirks::schip(), 5);
+    assert_eq!(lit_pixels(&machine), 0);
+
+    let quirks = Quirks {
+        lores_large_sprites: true,
+        ..Quirks::schip()
+    };
+    let machine = run_with(&rom, quirks, 5);
+    assert_eq!(lit_pixels(&machine), 60);
+    assert!(machine.get_pixel(19, 17));
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::run;
+
+// LD V0, 1; LD V1, 2; LD I, 300; LD [I], V1; DRW V0, V1, 5
+const ROM: [u8; 10] = [0x60, 0x01, 0x61, 0x02, 0xA3, 0x00, 0xF1, 0x55, 0xD0, 0x15];
+
+#[test]
+fn restore_rewinds_the_machine() {
+    let mut machine = run(&ROM, 2);
+    let snapshot = machine.snapshot();
+
+    for _ in 0..3 {
+        machine.execute_instruction().unwrap();
+    }
+    assert_ne!(machine.snapshot(), snapshot);
+
+    machine.restore(&snapshot);
+    assert_eq!(machine.snapshot(), snapshot);
+    assert_eq!(machine.program_counter(), 0x204);
+    assert_eq!(machine.read_memory(0x301), 0);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn applying_a_diff_reproduces_the_state() {
+    use chip8::chip8::Chip8;
+    use common::FixedRandom;
+
+    let mut machine = run(&ROM, 3);
+    let before = machine.snapshot();
+    let mut copy = Chip8::new(&ROM, FixedRandom(0)).unwrap();
+    copy.restore(&before);
+
+    // Stores two bytes and moves I
+    machine.execute_instruction().unwrap();
+    let delta = machine.diff(&before);
+    assert_eq!(delta.changed(), 2);
+
+    copy.apply_delta(&delta).unwrap();
+    assert_eq!(copy.snapshot(), machine.snapshot());
+
+    // Drawing changes the display
+    machine.execute_instruction().unwrap();
+    copy.apply_delta(&machine.diff(&copy.snapshot())).unwrap();
+    assert_eq!(copy.snapshot(), machine.snapshot());
+    assert_eq!(copy.get_display(), machine.get_display());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn diffing_snapshots_goes_back_in_time() {
+    let mut machine = run(&ROM, 3);
+    let before = machine.snapshot();
+    machine.execute_instruction().unwrap();
+    machine.execute_instruction().unwrap();
+    let after = machine.snapshot();
+
+    // From the newer state back to the older one
+    let back = before.diff(&after);
+    machine.apply_delta(&back).unwrap();
+    assert_eq!(machine.snapshot(), before);
+
+    // Forward again matches Chip8::diff
+    let forward = after.diff(&before);
+    assert_eq!(forward, run(&ROM, 5).diff(&before));
+    machine.apply_delta(&forward).unwrap();
+    assert_eq!(machine.snapshot(), after);
+}
+
+#[test]
+fn restore_keeps_the_held_keys() {
+    let mut machine = run(&ROM, 2);
+    let snapshot = machine.snapshot();
+
+    // Keys are host input, the key released since the snapshot stays released
+    machine.set_key(5, true);
+    machine.restore(&snapshot);
+    assert!(machine.keyboard_state()[5]);
+    machine.set_key(5, false);
+    machine.restore(&snapshot);
+    assert!(!machine.keyboard_state()[5]);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn deltas_past_the_end_of_memory_are_rejected() {
+    use chip8::chip8::{Chip8Builder, MAX_MEMORY_SIZE};
+    use chip8::error::Chip8Error;
+    use common::FixedRandom;
+
+    let mut large = Chip8Builder::new(&ROM, FixedRandom(0))
+        .memory_size(MAX_MEMORY_SIZE)
+        .build()
+        .unwrap();
+    let before = large.snapshot();
+    large.write_memory(0x2000, 0xAB);
+    let delta = large.diff(&before);
+
+    let mut machine = run(&ROM, 0);
+    let unchanged = machine.snapshot();
+    assert_eq!(
+        machine.apply_delta(&delta),
+        Err(Chip8Error::DeltaOutOfRange { addr: 0x2000 })
+    );
+    assert_eq!(machine.snapshot(), unchanged);
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use chip8::chip8::Chip8;
+use chip8::error::Chip8Error;
+use common::FixedRandom;
+
+#[test]
+fn recursion_guard_fires_at_configured_depth() {
+    // CALL 200, calls itself forever
+    let mut machine = Chip8::new(&[0x22, 0x00], FixedRandom(0)).unwrap();
+    machine.set_max_recursion_depth(Some(3));
+
+    for _ in 0..3 {
+        machine.execute_instruction().unwrap();
+    }
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::RecursionLimitExceeded { addr: 0x200 })
+    );
+}
+
+#[test]
+fn recursion_guard_is_off_by_default() {
+    let mut machine = Chip8::new(&[0x22, 0x00], FixedRandom(0)).unwrap();
+
+    for _ in 0..10 {
+        machine.execute_instruction().unwrap();
+    }
+}
+
+#[test]
+fn call_overflows_after_16_levels() {
+    // CALL 200, calls itself forever
+    let mut machine = Chip8::new(&[0x22, 0x00], FixedRandom(0)).unwrap();
+
+    for _ in 0..16 {
+        machine.execute_instruction().unwrap();
+    }
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::StackOverflow)
+    );
+    assert_eq!(machine.program_counter(), 0x200);
+}
+
+#[test]
+fn ret_with_empty_stack_underflows() {
+    // RET
+    let mut machine = Chip8::new(&[0x00, 0xEE], FixedRandom(0)).unwrap();
+
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::StackUnderflow)
+    );
+    assert_eq!(machine.program_counter(), 0x200);
+}
+
+/// CALL 208; LD V1, 1; JMP 206; then at 208: LD V0, 5; CALL 20e; RET;
+/// and at 20e: ADD V0, 1; RET
+const SUBROUTINE_ROM: [u8; 18] = [
+    0x22, 0x08, 0x61, 0x01, 0x00, 0x00, 0x12, 0x06, 0x60, 0x05, 0x22, 0x0E, 0x00, 0xEE, 0x70, 0x01,
+    0x00, 0xEE,
+];
+
+#[test]
+fn step_over_runs_the_call_to_completion() {
+    let mut machine = Chip8::new(&SUBROUTINE_ROM, FixedRandom(0)).unwrap();
+
+    machine.step_over(100).unwrap();
+    assert_eq!(machine.program_counter(), 0x202);
+    assert_eq!(machine.registers()[0], 6);
+    assert_eq!(machine.instruction_count(), 6);
+
+    // Anything else is a single step
+    machine.step_over(100).unwrap();
+    assert_eq!(machine.program_counter(), 0x204);
+    assert_eq!(machine.registers()[1], 1);
+}
+
+#[test]
+fn step_over_stops_early() {
+    let mut machine = Chip8::new(&SUBROUTINE_ROM, FixedRandom(0)).unwrap();
+    machine.step_over(3).unwrap();
+    assert_eq!(machine.program_counter(), 0x20E);
+
+    let mut machine = Chip8::new(&SUBROUTINE_ROM, FixedRandom(0)).unwrap();
+    machine.add_breakpoint(0x20C);
+    machine.step_over(100).unwrap();
+    assert_eq!(machine.program_counter(), 0x20C);
+}
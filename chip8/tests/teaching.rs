@@ -0,0 +1,44 @@
+mod common;
+
+use chip8::chip8::Chip8;
+use chip8::error::Chip8Error;
+use chip8::opcode::Opcode;
+use common::FixedRandom;
+
+/// LD V0, 1; LD V1, 2; ADD V0, V1
+const ROM: &[u8] = &[0x60, 0x01, 0x61, 0x02, 0x80, 0x14];
+
+#[test]
+fn disabled_opcode_is_not_executed() {
+    let mut machine = Chip8::new(ROM, FixedRandom(0)).unwrap();
+    assert!(machine.disable_opcode("8xy4"));
+
+    machine.execute_instruction().unwrap();
+    machine.execute_instruction().unwrap();
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::NotImplementedYet(Opcode::AddVxVy {
+            x: 0,
+            y: 1
+        }))
+    );
+    assert_eq!(machine.registers()[0], 1);
+}
+
+#[test]
+fn reenabled_opcode_executes() {
+    let mut machine = Chip8::new(ROM, FixedRandom(0)).unwrap();
+    assert!(machine.disable_opcode("8XY4"));
+    assert!(machine.enable_opcode("8xy4"));
+
+    for _ in 0..3 {
+        machine.execute_instruction().unwrap();
+    }
+    assert_eq!(machine.registers()[0], 3);
+}
+
+#[test]
+fn unknown_pattern_is_rejected() {
+    let mut machine = Chip8::new(ROM, FixedRandom(0)).unwrap();
+    assert!(!machine.disable_opcode("8xy9"));
+}
@@ -0,0 +1,216 @@
+mod common;
+
+use chip8::chip8::{instructions_per_frame, Chip8, Frame, MAX_BREAKPOINTS};
+use common::FixedRandom;
+
+#[test]
+fn step_frame_runs_cycles_then_ticks_timers() {
+    // LD V0, 2; LD ST, V0; LD DT, V0; LD V1, DT; JMP 208
+    let rom = [0x60, 0x02, 0xF0, 0x18, 0xF0, 0x15, 0xF1, 0x07, 0x12, 0x08];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+
+    assert!(machine.step_frame(4).unwrap());
+    assert_eq!(machine.program_counter(), 0x208);
+    // DT was read before the tick
+    assert_eq!(machine.registers()[1], 2);
+
+    assert!(machine.step_frame(1).unwrap());
+    assert!(!machine.step_frame(1).unwrap());
+}
+
+#[test]
+fn advance_reports_the_frame() {
+    // LD V0, 2; LD ST, V0; CLS; JMP 206
+    let rom = [0x60, 0x02, 0xF0, 0x18, 0x00, 0xE0, 0x12, 0x06];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+
+    assert_eq!(
+        machine.advance(3).unwrap(),
+        Frame {
+            executed: 3,
+            sound: true,
+            display_changed: true,
+        }
+    );
+    // Halted at the JMP, only the timers move
+    assert_eq!(machine.advance(3).unwrap().executed, 1);
+    assert!(machine.is_halted());
+    assert_eq!(
+        machine.advance(3).unwrap(),
+        Frame {
+            executed: 0,
+            sound: false,
+            display_changed: false,
+        }
+    );
+}
+
+#[test]
+fn advance_polls_a_blocked_key_wait() {
+    // LD V0, K; LD V1, 1
+    let rom = [0xF0, 0x0A, 0x61, 0x01];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+
+    assert_eq!(machine.advance(10).unwrap().executed, 1);
+    assert_eq!(machine.advance(10).unwrap().executed, 1);
+
+    machine.set_key(3, true);
+    machine.set_key(3, false);
+    assert_eq!(machine.advance(2).unwrap().executed, 2);
+    assert_eq!(machine.registers()[..2], [3, 1]);
+}
+
+#[test]
+fn instructions_per_frame_rounds_the_clock() {
+    assert_eq!(instructions_per_frame(600), 10);
+    assert_eq!(instructions_per_frame(500), 8);
+    assert_eq!(instructions_per_frame(0), 1);
+}
+
+#[test]
+fn step_leaves_timers_alone() {
+    // LD V0, 2; LD DT, V0; LD V1, DT; LD V1, DT
+    let rom = [0x60, 0x02, 0xF0, 0x15, 0xF1, 0x07, 0xF1, 0x07];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+
+    for _ in 0..4 {
+        machine.step().unwrap();
+    }
+    assert_eq!(machine.program_counter(), 0x208);
+    assert_eq!(machine.registers()[1], 2);
+}
+
+#[test]
+fn run_cycles_stops_on_key_wait() {
+    // LD V0, 1; LD V1, 2; LD V2, K; LD V3, 4
+    let rom = [0x60, 0x01, 0x61, 0x02, 0xF2, 0x0A, 0x63, 0x04];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+
+    assert_eq!(machine.run_cycles(10).unwrap(), 3);
+    assert!(machine.waiting_for_key());
+    assert_eq!(machine.program_counter(), 0x204);
+
+    // Still blocked
+    assert_eq!(machine.run_cycles(10).unwrap(), 1);
+
+    // Pressing isn't enough, the key has to be released too
+    machine.set_key(7, true);
+    assert_eq!(machine.run_cycles(10).unwrap(), 1);
+    assert!(machine.waiting_for_key());
+
+    machine.set_key(7, false);
+    assert_eq!(machine.run_cycles(2).unwrap(), 2);
+    assert!(!machine.waiting_for_key());
+    assert_eq!(machine.registers()[2], 7);
+    assert_eq!(machine.registers()[3], 4);
+}
+
+#[test]
+fn key_wait_picks_the_lowest_of_simultaneous_keys() {
+    // LD V0, K
+    let mut machine = Chip8::new(&[0xF0, 0x0A], FixedRandom(0)).unwrap();
+    assert_eq!(machine.run_cycles(1).unwrap(), 1);
+
+    machine.set_key(7, true);
+    machine.set_key(3, true);
+    // Auto-repeat sends the same state again
+    machine.set_key(7, true);
+    machine.run_cycles(1).unwrap();
+    assert!(machine.waiting_for_key());
+
+    // Still waiting for the lowest key to come up
+    machine.set_key(7, false);
+    machine.run_cycles(1).unwrap();
+    assert!(machine.waiting_for_key());
+
+    machine.set_key(3, false);
+    machine.run_cycles(1).unwrap();
+    assert!(!machine.waiting_for_key());
+    assert_eq!(machine.registers()[0], 3);
+    assert_eq!(machine.program_counter(), 0x202);
+}
+
+#[test]
+fn key_wait_sees_a_tap_between_polls() {
+    // LD V0, K
+    let mut machine = Chip8::new(&[0xF0, 0x0A], FixedRandom(0)).unwrap();
+    machine.run_cycles(1).unwrap();
+
+    machine.set_key(0xA, true);
+    machine.set_key(0xA, false);
+    machine.run_cycles(1).unwrap();
+    assert_eq!(machine.registers()[0], 0xA);
+}
+
+#[test]
+fn run_cycles_stops_at_breakpoints() {
+    // LD V0, 1; LD V1, 2; JMP 200
+    let rom = [0x60, 0x01, 0x61, 0x02, 0x12, 0x00];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+
+    assert!(machine.add_breakpoint(0x202));
+    assert_eq!(machine.run_cycles(10).unwrap(), 1);
+    assert_eq!(machine.program_counter(), 0x202);
+
+    // Resuming runs past the breakpoint until it is hit again
+    assert_eq!(machine.run_cycles(10).unwrap(), 3);
+    assert_eq!(machine.program_counter(), 0x202);
+
+    assert!(machine.remove_breakpoint(0x202));
+    assert!(!machine.remove_breakpoint(0x202));
+    assert_eq!(machine.run_cycles(10).unwrap(), 10);
+}
+
+#[test]
+fn breakpoint_capacity_is_limited() {
+    let mut machine = Chip8::new(&[], FixedRandom(0)).unwrap();
+    for i in 0..MAX_BREAKPOINTS as u16 {
+        assert!(machine.add_breakpoint(0x200 + i * 2));
+    }
+    assert!(!machine.add_breakpoint(0x300));
+    // Setting an existing breakpoint again is fine
+    assert!(machine.add_breakpoint(0x200));
+}
+
+#[test]
+fn self_jump_halts() {
+    // LD V0, 1; JMP 204; JMP 204
+    let rom = [0x60, 0x01, 0x12, 0x04, 0x12, 0x04];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+
+    // A jump to somewhere else doesn't halt
+    assert_eq!(machine.run_cycles(2).unwrap(), 2);
+    assert!(!machine.is_halted());
+
+    assert_eq!(machine.run_cycles(10).unwrap(), 1);
+    assert!(machine.is_halted());
+    assert_eq!(machine.program_counter(), 0x204);
+
+    machine.reset();
+    assert!(!machine.is_halted());
+}
+
+#[test]
+fn timer_queries_do_not_tick() {
+    // LD V0, 3; LD ST, V0; LD V0, 5; LD DT, V0
+    let rom = [0x60, 0x03, 0xF0, 0x18, 0x60, 0x05, 0xF0, 0x15];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+    machine.run_cycles(4).unwrap();
+
+    for _ in 0..3 {
+        assert!(machine.sound_active());
+        assert_eq!(machine.sound_timer(), 3);
+        assert_eq!(machine.delay_timer(), 5);
+    }
+
+    machine.tick_timers();
+    assert_eq!(machine.sound_timer(), 2);
+    assert_eq!(machine.delay_timer(), 4);
+
+    for _ in 0..4 {
+        machine.tick_timers();
+    }
+    assert!(!machine.sound_active());
+    assert_eq!(machine.sound_timer(), 0);
+    assert_eq!(machine.delay_timer(), 0);
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::io::{self, Write};
+
+use chip8::chip8::Chip8;
+use common::{FixedRandom, SharedBuffer};
+
+#[test]
+fn writes_a_line_before_each_instruction() {
+    // LD V0, 5; LD I, 2AB; CALL 208; JP 206; RET
+    let rom = [0x60, 0x05, 0xA2, 0xAB, 0x22, 0x08, 0x12, 0x06, 0x00, 0xEE];
+    let mut machine = Chip8::new(&rom, FixedRandom(0)).unwrap();
+    let buffer = SharedBuffer::default();
+    machine.set_trace_writer(buffer.clone());
+
+    for _ in 0..4 {
+        machine.execute_instruction().unwrap();
+    }
+
+    let text = buffer.text();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+        lines[0],
+        "0200 6005 0000 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00"
+    );
+    assert_eq!(
+        lines[1],
+        "0202 a2ab 0000 00 05 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00"
+    );
+    assert!(lines[3].starts_with("0208 00ee 02ab 01 05 "));
+
+    // Nothing more once tracing stops
+    machine.clear_trace_writer().unwrap();
+    machine.execute_instruction().unwrap();
+    assert_eq!(buffer.text(), text);
+}
+
+struct FailingWriter;
+
+impl Write for FailingWriter {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_errors_stop_execution() {
+    let mut machine = Chip8::new(&[0x60, 0x05], FixedRandom(0)).unwrap();
+    machine.set_trace_writer(FailingWriter);
+
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(chip8::error::Chip8Error::Io(io::ErrorKind::Other))
+    );
+    assert_eq!(machine.program_counter(), 0x200);
+}
+
+#[test]
+fn tracing_machines_can_move_between_threads() {
+    let mut machine = Chip8::new(&[0x60, 0x05], FixedRandom(0)).unwrap();
+    let buffer = SharedBuffer::default();
+    machine.set_trace_writer(buffer.clone());
+
+    std::thread::spawn(move || machine.execute_instruction().unwrap())
+        .join()
+        .unwrap();
+    assert!(buffer.text().starts_with("0200 6005"));
+}
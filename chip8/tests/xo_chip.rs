@@ -0,0 +1,115 @@
+mod common;
+
+use chip8::chip8::Chip8;
+use chip8::error::Chip8Error;
+use chip8::quirks::Quirks;
+use common::{run_with, FixedRandom};
+
+#[test]
+fn extensions_are_invalid_in_chip8_mode() {
+    let mut machine = Chip8::new(&[0xF0, 0x00, 0x12, 0x34], FixedRandom(0)).unwrap();
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::InvalidInstruction(0xF000))
+    );
+
+    let mut machine = Chip8::new(&[0xF3, 0x01], FixedRandom(0)).unwrap();
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::InvalidInstruction(0xF301))
+    );
+}
+
+#[test]
+fn long_load_sets_i_and_skips_the_address() {
+    // LD I, LONG 0x1234
+    let machine = run_with(&[0xF0, 0x00, 0x12, 0x34], Quirks::xo_chip(), 1);
+    assert_eq!(machine.index_register(), 0x1234);
+    assert_eq!(machine.program_counter(), 0x204);
+}
+
+#[test]
+fn skip_steps_over_long_load() {
+    // SE V0, 0; LD I, LONG 0x1234
+    let machine = run_with(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34], Quirks::xo_chip(), 1);
+    assert_eq!(machine.program_counter(), 0x206);
+}
+
+#[test]
+fn planes_draw_their_own_data() {
+    // PLANE 3; LD I, 0x20A; DRW V0, V0, 1; sprite rows 0xF0 (plane 1), 0x3C (plane 2)
+    let rom = [
+        0xF3, 0x01, 0xA2, 0x0A, 0xD0, 0x01, 0x00, 0x00, 0x00, 0x00, 0xF0, 0x3C,
+    ];
+    let machine = run_with(&rom, Quirks::xo_chip(), 3);
+
+    assert_eq!(machine.get_display()[..8], [1, 1, 3, 3, 2, 2, 0, 0]);
+    assert_eq!(machine.registers()[0xF], 0);
+
+    let indices: Vec<u8> = (0..8).map(|x| machine.get_pixel_index(x, 0)).collect();
+    assert_eq!(indices, machine.get_display()[..8]);
+    assert!(machine.get_pixel(4, 0));
+    assert!(!machine.get_pixel(6, 0));
+}
+
+#[test]
+fn collisions_only_check_selected_planes() {
+    // PLANE 3; LD I, 0x20C; DRW V0, V0, 1; PLANE 2; DRW V0, V0, 1; sprite 0xF0, 0xF0
+    let rom = [
+        0xF3, 0x01, 0xA2, 0x0C, 0xD0, 0x01, 0xF2, 0x01, 0xD0, 0x01, 0x00, 0x00, 0xF0, 0xF0,
+    ];
+
+    // Redrawing on plane 2 only erases plane 2
+    let machine = run_with(&rom, Quirks::xo_chip(), 5);
+    assert_eq!(machine.get_display()[..5], [1, 1, 1, 1, 0]);
+    assert_eq!(machine.registers()[0xF], 1);
+}
+
+#[test]
+fn clear_only_clears_selected_planes() {
+    // PLANE 3; LD I, 0x20A; DRW V0, V0, 1; PLANE 1; CLS; sprite 0xF0, 0xF0
+    let rom = [
+        0xF3, 0x01, 0xA2, 0x0A, 0xD0, 0x01, 0xF1, 0x01, 0x00, 0xE0, 0xF0, 0xF0,
+    ];
+
+    let machine = run_with(&rom, Quirks::xo_chip(), 5);
+    assert_eq!(machine.get_display()[..5], [2, 2, 2, 2, 0]);
+}
+
+#[test]
+fn audio_loads_the_pattern_and_pitch() {
+    // LD I, 208; AUDIO; LD V3, 70; PITCH V3, then the pattern
+    let mut rom = vec![0xA2, 0x08, 0xF0, 0x02, 0x63, 0x70, 0xF3, 0x3A];
+    rom.extend(0..16);
+
+    let machine = run_with(&rom, Quirks::xo_chip(), 0);
+    assert!(!machine.has_audio_pattern());
+    assert_eq!(machine.audio_buffer(), &[0; 16]);
+    assert_eq!(machine.pitch(), 64);
+
+    let machine = run_with(&rom, Quirks::xo_chip(), 4);
+    assert!(machine.has_audio_pattern());
+    assert_eq!(machine.audio_buffer()[..], rom[8..]);
+    assert_eq!(machine.index_register(), 0x208);
+    assert_eq!(machine.pitch(), 0x70);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn pattern_rate_follows_the_pitch() {
+    // LD V0, 0x70, one octave above the default of 64; PITCH V0
+    let machine = run_with(&[0x60, 0x70, 0xF0, 0x3A], Quirks::xo_chip(), 0);
+    assert_eq!(machine.pattern_rate(), 4000.0);
+
+    let machine = run_with(&[0x60, 0x70, 0xF0, 0x3A], Quirks::xo_chip(), 2);
+    assert_eq!(machine.pattern_rate(), 8000.0);
+}
+
+#[test]
+fn audio_is_invalid_in_chip8_mode() {
+    let mut machine = Chip8::new(&[0xF0, 0x02], FixedRandom(0)).unwrap();
+    assert_eq!(
+        machine.execute_instruction(),
+        Err(Chip8Error::InvalidInstruction(0xF002))
+    );
+}
@@ -0,0 +1,151 @@
+//! Batch mode, runs a ROM for a number of frames without a window and dumps
+//! the final state
+//!
+//! The dump is plain text so it can be checked in and diffed, e.g. to catch
+//! opcode regressions in CI. After the display it lists the whole memory, 32
+//! bytes per line. The start of the dump for the ROM in the tests:
+//!
+//! ```text
+//! frames 2
+//! pc 0x020a opcode 0x00ee RET
+//! v 00 05 00 00 00 00 00 00 00 00 00 00 00 00 00 00
+//! i 0x0019 sp 1 delay 0 sound 0
+//! stack 0x0204
+//! display 64x32
+//! f000000000000000
+//! 8000000000000000
+//! ```
+
+use std::error::Error;
+use std::io::{self, Write};
+
+use ::chip8::chip8::Chip8;
+use ::chip8::io::Random;
+
+use crate::replay::Input;
+
+/// Memory bytes per line of the dump
+const MEMORY_ROW: usize = 32;
+
+/// Run `frames` frames, stopping early on an error
+///
+/// Returns the number of frames completed along with the error, if any.
+pub fn run<R: Random>(
+    machine: &mut Chip8<R>,
+    input: &mut Input,
+    frames: usize,
+    cycles_per_frame: usize,
+) -> (usize, Result<(), Box<dyn Error>>) {
+    for frame in 0..frames {
+        input.before_frame(machine);
+        if let Err(e) = machine.advance(cycles_per_frame) {
+            return (frame, Err(e.into()));
+        }
+    }
+    (frames, Ok(()))
+}
+
+/// Write the registers, stack, timers, packed display and memory
+pub fn dump<R: Random>(machine: &Chip8<R>, frames: usize, out: &mut impl Write) -> io::Result<()> {
+    let snapshot = machine.debug_snapshot();
+    writeln!(out, "frames {}", frames)?;
+    writeln!(
+        out,
+        "pc 0x{:04x} opcode 0x{:04x} {}",
+        snapshot.pc, snapshot.opcode, snapshot.mnemonic
+    )?;
+    writeln!(out, "v {}", hex_bytes(&snapshot.registers))?;
+    writeln!(
+        out,
+        "i 0x{:04x} sp {} delay {} sound {}",
+        snapshot.i, snapshot.sp, snapshot.delay, snapshot.sound
+    )?;
+    let stack: Vec<String> = snapshot.stack[..snapshot.sp as usize]
+        .iter()
+        .map(|addr| format!(" 0x{:04x}", addr))
+        .collect();
+    writeln!(out, "stack{}", stack.concat())?;
+
+    let (width, height) = machine.display_dimensions();
+    let mut packed = vec![0; width * height / 8];
+    machine
+        .get_display_packed(&mut packed)
+        .map_err(io::Error::other)?;
+    writeln!(out, "display {}x{}", width, height)?;
+    for row in packed.chunks_exact(width / 8) {
+        let line: String = row.iter().map(|byte| format!("{:02x}", byte)).collect();
+        writeln!(out, "{}", line)?;
+    }
+
+    let memory: Vec<u8> = (0..machine.memory_size())
+        .map(|addr| machine.read_memory(addr as u16))
+        .collect();
+    writeln!(out, "memory {}", memory.len())?;
+    for row in memory.chunks(MEMORY_ROW) {
+        let line: String = row.iter().map(|byte| format!("{:02x}", byte)).collect();
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    hex.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::chip8::io::SeededRandom;
+
+    // LD V1, 5; CALL 206; JP 204; LD F, V1; DRW V0, V0, 5; RET
+    const ROM: [u8; 12] = [
+        0x61, 0x05, 0x22, 0x06, 0x12, 0x04, 0xF1, 0x29, 0xD0, 0x05, 0x00, 0xEE,
+    ];
+
+    #[test]
+    fn dumps_the_final_state() {
+        let mut machine = Chip8::new(&ROM, SeededRandom::new(0)).unwrap();
+        let (frames, result) = run(&mut machine, &mut Input::Live, 2, 2);
+        assert_eq!(frames, 2);
+        assert!(result.is_ok());
+
+        let mut out = Vec::new();
+        dump(&machine, frames, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[0], "frames 2");
+        assert_eq!(lines[1], "pc 0x020a opcode 0x00ee RET");
+        assert_eq!(
+            lines[2],
+            "v 00 05 00 00 00 00 00 00 00 00 00 00 00 00 00 00"
+        );
+        assert_eq!(lines[3], "i 0x0019 sp 1 delay 0 sound 0");
+        assert_eq!(lines[4], "stack 0x0204");
+        assert_eq!(lines[5], "display 64x32");
+        assert_eq!(lines.len(), 6 + 32 + 1 + 4096 / MEMORY_ROW);
+        // The "5" glyph in the top left corner
+        let rows: Vec<String> = ["f0", "80", "f0", "10", "f0"]
+            .iter()
+            .map(|row| format!("{:0<16}", row))
+            .collect();
+        assert_eq!(lines[6..11], rows[..]);
+        assert!(lines[11..38].iter().all(|line| *line == "0".repeat(16)));
+
+        assert_eq!(lines[38], "memory 4096");
+        // The ROM starts the row at 0x200
+        let rom_row = 39 + 0x200 / MEMORY_ROW;
+        assert!(lines[rom_row].starts_with("610522061204f129d00500ee"));
+        assert_eq!(lines[rom_row].len(), MEMORY_ROW * 2);
+    }
+
+    #[test]
+    fn stops_at_the_first_error() {
+        // An invalid instruction
+        let mut machine = Chip8::new(&[0xFF, 0xFF], SeededRandom::new(0)).unwrap();
+        let (frames, result) = run(&mut machine, &mut Input::Live, 10, 2);
+        assert_eq!(frames, 0);
+        assert!(result.is_err());
+    }
+}
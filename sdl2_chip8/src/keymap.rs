@@ -0,0 +1,179 @@
+//! Keyboard and controller to CHIP-8 keypad mapping
+
+use std::collections::HashMap;
+
+use sdl2::controller::Button;
+use sdl2::keyboard::Keycode;
+
+/// Host keys for CHIP-8 keys 0 to F
+const DEFAULT_KEYS: [Keycode; 16] = [
+    Keycode::Kp7,
+    Keycode::Kp8,
+    Keycode::Kp9,
+    Keycode::Kp4,
+    Keycode::Kp5,
+    Keycode::Kp6,
+    Keycode::Kp1,
+    Keycode::Kp2,
+    Keycode::Kp3,
+    Keycode::Q,
+    Keycode::W,
+    Keycode::E,
+    Keycode::R,
+    Keycode::A,
+    Keycode::S,
+    Keycode::D,
+];
+
+/// Maps host keys to keypad indices
+pub struct Keymap {
+    keys: HashMap<Keycode, u8>,
+}
+
+impl Keymap {
+    fn from_keys(keys: &[Keycode]) -> Self {
+        Keymap {
+            keys: keys
+                .iter()
+                .enumerate()
+                .map(|(index, key)| (*key, index as u8))
+                .collect(),
+        }
+    }
+
+    /// Parse 16 comma separated SDL key names, for CHIP-8 keys 0 to F
+    ///
+    /// Each host key may only be bound once.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let keys = spec
+            .split(',')
+            .map(|name| {
+                let name = name.trim();
+                Keycode::from_name(name)
+                    .map(|key| (name, key))
+                    .ok_or_else(|| format!("unknown key: {}", name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_named_keys(&keys)
+    }
+
+    /// Build from keys paired with the names they were parsed from, which
+    /// the errors use
+    fn from_named_keys(keys: &[(&str, Keycode)]) -> Result<Self, String> {
+        if keys.len() != 16 {
+            return Err(format!("expected 16 keys, got {}", keys.len()));
+        }
+        for (index, (name, key)) in keys.iter().enumerate() {
+            if let Some(first) = keys[..index].iter().position(|(_, other)| other == key) {
+                return Err(format!(
+                    "key {} is bound to both {:X} and {:X}",
+                    name, first, index
+                ));
+            }
+        }
+        let keys: Vec<Keycode> = keys.iter().map(|&(_, key)| key).collect();
+        Ok(Self::from_keys(&keys))
+    }
+
+    /// The keypad index for a host key, if it is mapped
+    pub fn map_key(&self, key: Keycode) -> Option<u8> {
+        self.keys.get(&key).copied()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_keys(&DEFAULT_KEYS)
+    }
+}
+
+/// Controller buttons and their default keys, the D-pad moves with 2, 4, 6
+/// and 8, the common movement keys, and the face buttons act
+const DEFAULT_BUTTONS: [(Button, u8); 8] = [
+    (Button::DPadUp, 0x2),
+    (Button::DPadDown, 0x8),
+    (Button::DPadLeft, 0x4),
+    (Button::DPadRight, 0x6),
+    (Button::A, 0x5),
+    (Button::B, 0x0),
+    (Button::X, 0xA),
+    (Button::Y, 0xB),
+];
+
+/// Maps controller buttons to keypad indices
+pub struct ButtonMap {
+    buttons: HashMap<Button, u8>,
+}
+
+impl ButtonMap {
+    /// Parse comma separated `button=key` pairs, e.g. `dpup=2,a=5`
+    ///
+    /// Buttons use the SDL names, keys are a hex digit. Buttons that are not
+    /// listed are unmapped.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let buttons = spec
+            .split(',')
+            .map(|pair| {
+                let (name, key) = pair
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected button=key, got {:?}", pair))?;
+                let button = Button::from_string(name.trim())
+                    .ok_or_else(|| format!("unknown button: {}", name))?;
+                let key = u8::from_str_radix(key.trim(), 16)
+                    .ok()
+                    .filter(|&key| key < 16)
+                    .ok_or_else(|| format!("invalid key: {}", key))?;
+                Ok((button, key))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+
+        Ok(ButtonMap { buttons })
+    }
+
+    /// The keypad index for a controller button, if it is mapped
+    pub fn map_button(&self, button: Button) -> Option<u8> {
+        self.buttons.get(&button).copied()
+    }
+}
+
+impl Default for ButtonMap {
+    fn default() -> Self {
+        ButtonMap {
+            buttons: DEFAULT_BUTTONS.iter().copied().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_buttons() {
+        let buttons = ButtonMap::default();
+        assert_eq!(buttons.map_button(Button::DPadUp), Some(0x2));
+        assert_eq!(buttons.map_button(Button::A), Some(0x5));
+        assert_eq!(buttons.map_button(Button::Start), None);
+    }
+
+    #[test]
+    fn default_layout() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.map_key(Keycode::Kp7), Some(0x0));
+        assert_eq!(keymap.map_key(Keycode::Q), Some(0x9));
+        assert_eq!(keymap.map_key(Keycode::D), Some(0xF));
+        assert_eq!(keymap.map_key(Keycode::Z), None);
+    }
+
+    #[test]
+    fn rejects_keys_bound_twice() {
+        let mut keys: Vec<(&str, Keycode)> = DEFAULT_KEYS.iter().map(|&key| ("", key)).collect();
+        assert!(Keymap::from_named_keys(&keys).is_ok());
+
+        keys[0xA] = ("Keypad 8", Keycode::Kp8);
+        assert_eq!(
+            Keymap::from_named_keys(&keys).err(),
+            Some("key Keypad 8 is bound to both 1 and A".to_string())
+        );
+    }
+}
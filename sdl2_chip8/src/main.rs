@@ -1,24 +1,42 @@
-use core::time;
-use std::{env, error::Error, fs::File, io::Read};
+mod headless;
+mod keymap;
+mod options;
+mod palette;
+mod replay;
+mod rewind;
+mod screenshot;
+mod timing;
+
+use std::time::Instant;
+use std::{env, error::Error, fs::File};
 
 use ::chip8::io::Random;
+use ::chip8::quirks::{Quirks, PRESETS};
 use ::chip8::*;
+use options::Options;
 use rand::prelude::*;
 use rand::Rng;
+use replay::{Input, Recording};
+use rewind::{Rewind, REWIND_FRAMES};
 use sdl2::audio::AudioCallback;
 use sdl2::audio::AudioSpecDesired;
-use sdl2::{event::Event, keyboard::Keycode, pixels::PixelFormatEnum};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::{keyboard::Keycode, pixels::PixelFormatEnum};
+use timing::FrameClock;
 
 const SCALE: usize = 20;
 
+/// Most instructions a step over runs, in case the subroutine never returns
+const STEP_OVER_LIMIT: usize = 100_000;
+
 struct RandomNum {
-    rng: ThreadRng,
+    rng: StdRng,
 }
 
 impl RandomNum {
-    fn new() -> Self {
+    fn new(seed: u64) -> Self {
         Self {
-            rng: rand::thread_rng(),
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 }
@@ -29,64 +47,145 @@ impl Random for RandomNum {
     }
 }
 
-struct SquareWave {
-    phase_inc: f32,
+/// XO-CHIP audio pattern and its samples per second
+type Pattern = ([u8; 16], f32);
+
+/// Plays a square wave, or the ROM's audio pattern once it set one
+struct Beeper {
+    sample_rate: f32,
+    tone: f32,
     phase: f32,
     volume: f32,
+    pattern: Option<Pattern>,
 }
 
-impl AudioCallback for SquareWave {
+impl AudioCallback for Beeper {
     type Channel = f32;
 
     fn callback(&mut self, out: &mut [f32]) {
-        // Generate a square wave
         for x in out.iter_mut() {
-            *x = if self.phase <= 0.5 {
-                self.volume
-            } else {
-                -self.volume
+            // Phase goes over one period of the square wave or one loop of
+            // the 128 bit pattern
+            let (high, phase_inc) = match &self.pattern {
+                Some((bits, rate)) => {
+                    let bit = (self.phase * 128.0) as usize % 128;
+                    let high = bits[bit / 8] & (0x80 >> (bit % 8)) != 0;
+                    (high, rate / 128.0 / self.sample_rate)
+                }
+                None => (self.phase <= 0.5, self.tone / self.sample_rate),
             };
-            self.phase = (self.phase + self.phase_inc) % 1.0;
+            *x = if high { self.volume } else { -self.volume };
+            self.phase = (self.phase + phase_inc) % 1.0;
         }
     }
 }
 
-#[allow(non_snake_case)]
 fn print_debug_info(machine: &chip8::Chip8<RandomNum>) {
-    let (PC, instruction, V, I) = machine.get_debug_info();
+    let snapshot = machine.debug_snapshot();
     println!(
         "{:x} {:x} {}",
-        PC,
-        instruction,
-        chip8::Chip8::<RandomNum>::print_instruction(instruction)
+        snapshot.pc, snapshot.opcode, snapshot.mnemonic
     );
-    for r in V.iter().take(15) {
+    for r in snapshot.registers.iter().take(15) {
         print!("{} ", r);
     }
-    println!("I: {}", I);
+    println!("I: {}", snapshot.i);
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        println!("Usage: chip8_sdl2 [rom_file]");
-        std::process::exit(1);
-    }
-    let mut file = File::open(&args[1]).unwrap();
-    let mut data = Vec::new();
-    file.read_to_end(&mut data).unwrap();
+    let mut options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            println!("{}\n{}", e, options::USAGE);
+            std::process::exit(1);
+        }
+    };
+    let mut file = File::open(&options.rom).unwrap();
 
-    let random = RandomNum::new();
+    // A replay brings its own seed, speed, quirks and strictness
+    let (seed, input) = match &options.replay {
+        Some(path) => {
+            let recording = Recording::load(path).unwrap();
+            options.cycles_per_frame = recording.cycles_per_frame;
+            options.quirks = recording.quirks;
+            options.strict = recording.strict;
+            let input = Input::Replay {
+                events: recording.events,
+                next: 0,
+            };
+            (recording.seed, input)
+        }
+        None => {
+            // Batch runs should be reproducible out of the box
+            let seed = match options.headless_frames {
+                Some(_) => options.seed.unwrap_or(0),
+                None => options.seed.unwrap_or_else(rand::random),
+            };
+            let input = match &options.record {
+                Some(path) => Input::Record {
+                    path: path.clone(),
+                    recording: Recording::new(
+                        seed,
+                        options.cycles_per_frame,
+                        options.quirks,
+                        options.strict,
+                    )
+                    .unwrap(),
+                },
+                None => Input::Live,
+            };
+            (seed, input)
+        }
+    };
+
+    let random = RandomNum::new(seed);
+
+    let mut chip8 = match chip8::Chip8::from_reader(&mut file, random) {
+        Ok(chip8) => chip8.with_quirks(options.quirks),
+        Err(e) => {
+            println!("{}: {}", options.rom, e);
+            std::process::exit(1);
+        }
+    };
 
-    let chip8 = chip8::Chip8::new(&data, random);
+    chip8.set_strict_alignment(options.strict);
 
-    run(chip8).unwrap();
+    if let Some(frames) = options.headless_frames {
+        run_headless(chip8, &options, input, frames);
+    } else {
+        run(chip8, &options, input).unwrap();
+    }
 }
 
-fn run(mut machine: chip8::Chip8<RandomNum>) -> Result<(), Box<dyn Error>> {
+/// Run without a window and print the final state, exits with 1 on errors
+fn run_headless(
+    mut machine: chip8::Chip8<RandomNum>,
+    options: &Options,
+    mut input: Input,
+    frames: usize,
+) {
+    let (completed, result) =
+        headless::run(&mut machine, &mut input, frames, options.cycles_per_frame);
+
+    // The state is still useful to see where a failing ROM stopped
+    headless::dump(&machine, completed, &mut std::io::stdout()).unwrap();
+    if let Err(e) = result.and_then(|_| input.finish()) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(
+    mut machine: chip8::Chip8<RandomNum>,
+    options: &Options,
+    mut input: Input,
+) -> Result<(), Box<dyn Error>> {
     let sdl_context = sdl2::init()?;
     let video = sdl_context.video()?;
     let audio = sdl_context.audio()?;
+    let controller_subsystem = sdl_context.game_controller()?;
+    // Open controllers stay connected until dropped
+    let mut controllers = Vec::new();
 
     // Initialize audio device
     let desired_spec = AudioSpecDesired {
@@ -95,12 +194,14 @@ fn run(mut machine: chip8::Chip8<RandomNum>) -> Result<(), Box<dyn Error>> {
         samples: None,     // default sample size
     };
 
-    let device = audio.open_playback(None, &desired_spec, |spec| {
+    let mut device = audio.open_playback(None, &desired_spec, |spec| {
         // initialize the audio callback
-        SquareWave {
-            phase_inc: 440.0 / spec.freq as f32,
+        Beeper {
+            sample_rate: spec.freq as f32,
+            tone: options.tone,
             phase: 0.0,
-            volume: 0.25,
+            volume: options.volume,
+            pattern: None,
         }
     })?;
 
@@ -118,18 +219,29 @@ fn run(mut machine: chip8::Chip8<RandomNum>) -> Result<(), Box<dyn Error>> {
 
     let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
 
+    // The texture matches the display resolution and is stretched over the window
     let texture_creator = canvas.texture_creator();
-    let mut tex_display = texture_creator
-        .create_texture_streaming(
-            PixelFormatEnum::RGB24,
-            chip8::SCREEN_WIDTH as u32,
-            chip8::SCREEN_HEIGHT as u32,
-        )
-        .map_err(|e| e.to_string())?;
+    let create_texture = |(width, height): (usize, usize)| {
+        texture_creator
+            .create_texture_streaming(PixelFormatEnum::RGB24, width as u32, height as u32)
+            .map_err(|e| e.to_string())
+    };
+    let mut tex_dimensions = machine.display_dimensions();
+    let mut tex_display = create_texture(tex_dimensions)?;
 
     let mut event_pump = sdl_context.event_pump()?;
 
+    let mut clock = FrameClock::new();
+    let mut last_time = Instant::now();
+    let mut paused = false;
+    // Rewinding would desync the instruction counts replays are keyed on
+    let mut rewind = matches!(input, Input::Live).then(|| Rewind::new(&machine, REWIND_FRAMES));
+    let mut rewinding = false;
+
     'gameloop: loop {
+        // The window contents are lost when it gets exposed
+        let mut exposed = false;
+
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. }
@@ -138,92 +250,208 @@ fn run(mut machine: chip8::Chip8<RandomNum>) -> Result<(), Box<dyn Error>> {
                     ..
                 } => break 'gameloop,
                 Event::KeyDown {
-                    keycode: Some(key), ..
+                    keycode: Some(Keycode::F12),
+                    repeat: false,
+                    ..
                 } => {
-                    let index = match key {
-                        Keycode::Kp7 => 0,
-                        Keycode::Kp8 => 1,
-                        Keycode::Kp9 => 2,
-                        Keycode::Kp4 => 3,
-                        Keycode::Kp5 => 4,
-                        Keycode::Kp6 => 5,
-                        Keycode::Kp1 => 6,
-                        Keycode::Kp2 => 7,
-                        Keycode::Kp3 => 8,
-                        Keycode::Q => 9,
-                        Keycode::W => 10,
-                        Keycode::E => 11,
-                        Keycode::R => 12,
-                        Keycode::A => 13,
-                        Keycode::S => 14,
-                        Keycode::D => 15,
-                        _ => 16,
-                    };
-                    machine.set_key(index, true);
+                    // A screenshot that can't be written isn't worth stopping for
+                    let path = screenshot::timestamped_name();
+                    match screenshot::save_png(
+                        &path,
+                        machine.get_display(),
+                        machine.display_dimensions(),
+                        SCALE,
+                        &options.palette,
+                    ) {
+                        Ok(()) => println!("Saved screenshot to {}", path),
+                        Err(e) => eprintln!("Couldn't save screenshot to {}: {}", path, e),
+                    }
+                }
+                // Presets and single steps aren't part of a recording
+                Event::KeyDown {
+                    keycode: Some(Keycode::F5),
+                    repeat: false,
+                    ..
+                } if !input.is_deterministic() => {
+                    // Custom quirks that match no preset start over at the first,
+                    // --display-wait stays on across presets
+                    let display_wait = machine.quirks().display_wait;
+                    let next = PRESETS
+                        .iter()
+                        .position(|(_, quirks)| {
+                            Quirks {
+                                display_wait,
+                                ..*quirks
+                            } == *machine.quirks()
+                        })
+                        .map_or(0, |index| (index + 1) % PRESETS.len());
+                    let (name, quirks) = PRESETS[next];
+                    machine.set_quirks(Quirks {
+                        display_wait,
+                        ..quirks
+                    });
+                    println!("Quirks: {}", name);
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Space),
+                    repeat: false,
+                    ..
+                } => {
+                    paused = !paused;
+                    if paused {
+                        device.pause();
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::N),
+                    ..
+                } if paused && !input.is_deterministic() => {
+                    print_debug_info(&machine);
+                    input.before_frame(&mut machine);
+                    machine.step().inspect_err(|_| print_debug_info(&machine))?;
+                    if let Some(rewind) = &mut rewind {
+                        rewind.push(&machine);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::O),
+                    ..
+                } if paused && !input.is_deterministic() => {
+                    print_debug_info(&machine);
+                    input.before_frame(&mut machine);
+                    machine
+                        .step_over(STEP_OVER_LIMIT)
+                        .inspect_err(|_| print_debug_info(&machine))?;
+                    if let Some(rewind) = &mut rewind {
+                        rewind.push(&machine);
+                    }
+                }
+                // While paused every press, including repeats, goes back one frame
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                } if paused => {
+                    if let Some(rewind) = &mut rewind {
+                        rewind.rewind(&mut machine);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                } => rewinding = true,
+                Event::KeyUp {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                } => rewinding = false,
+                // OS auto-repeat would only record redundant presses
+                Event::KeyDown {
+                    keycode: Some(key),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(index) = options.keymap.map_key(key) {
+                        input.key(&mut machine, index, true);
+                    }
                 }
                 Event::KeyUp {
                     keycode: Some(key), ..
                 } => {
-                    let index = match key {
-                        Keycode::Kp7 => 0,
-                        Keycode::Kp8 => 1,
-                        Keycode::Kp9 => 2,
-                        Keycode::Kp4 => 3,
-                        Keycode::Kp5 => 4,
-                        Keycode::Kp6 => 5,
-                        Keycode::Kp1 => 6,
-                        Keycode::Kp2 => 7,
-                        Keycode::Kp3 => 8,
-                        Keycode::Q => 9,
-                        Keycode::W => 10,
-                        Keycode::E => 11,
-                        Keycode::R => 12,
-                        Keycode::A => 13,
-                        Keycode::S => 14,
-                        Keycode::D => 15,
-                        _ => 16,
-                    };
-                    machine.set_key(index, false)
+                    if let Some(index) = options.keymap.map_key(key) {
+                        input.key(&mut machine, index, false);
+                    }
+                }
+                // Also sent for controllers connected before startup
+                Event::ControllerDeviceAdded { which, .. } => {
+                    match controller_subsystem.open(which) {
+                        Ok(controller) => controllers.push(controller),
+                        Err(e) => println!("Couldn't open controller {}: {}", which, e),
+                    }
+                }
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    controllers.retain(|controller| controller.instance_id() != which)
+                }
+                Event::ControllerButtonDown { button, .. } => {
+                    if let Some(index) = options.buttons.map_button(button) {
+                        input.key(&mut machine, index, true);
+                    }
+                }
+                Event::ControllerButtonUp { button, .. } => {
+                    if let Some(index) = options.buttons.map_button(button) {
+                        input.key(&mut machine, index, false);
+                    }
                 }
+                Event::Window {
+                    win_event: WindowEvent::Exposed,
+                    ..
+                } => exposed = true,
                 _ => {}
             }
         }
 
-        print_debug_info(&machine);
+        let now = Instant::now();
+        let elapsed = now - last_time;
+        last_time = now;
 
-        for _ in 0..10 {
-            machine.execute_instruction();
-        }
+        // Time spent paused is dropped, so unpausing doesn't catch up on it
+        let frames = if paused { 0 } else { clock.advance(elapsed) };
 
-        machine.decrement_delay();
+        // Timers tick once per simulated frame, independent of the render rate
+        let mut display_changed = false;
+        for _ in 0..frames {
+            // Holding the rewind key plays the recorded frames backwards,
+            // the machine stays on the oldest one once they run out
+            if let Some(rewind) = rewind.as_mut().filter(|_| rewinding) {
+                rewind.rewind(&mut machine);
+                device.pause();
+                continue;
+            }
 
-        if machine.sound_tick() {
-            device.resume();
-        } else {
-            device.pause()
-        }
+            print_debug_info(&machine);
+            input.before_frame(&mut machine);
 
-        tex_display.with_lock(None, |buffer: &mut [u8], _pitch: usize| {
-            for y in 0..chip8::SCREEN_HEIGHT {
-                for x in 0..chip8::SCREEN_WIDTH {
-                    let pixel = machine.get_pixel(x, y);
+            // Show where things went wrong, PC stays on the failing instruction
+            let frame = machine
+                .advance(options.cycles_per_frame)
+                .inspect_err(|_| print_debug_info(&machine))?;
+            display_changed |= frame.display_changed;
+            if let Some(rewind) = &mut rewind {
+                rewind.push(&machine);
+            }
 
-                    let color: u8 = if pixel { 255 } else { 0 };
-                    let pos = (y * chip8::SCREEN_WIDTH + x) * 3;
+            // The audio thread picks up the XO-CHIP pattern and pitch
+            device.lock().pattern = Some((*machine.audio_buffer(), machine.pattern_rate()))
+                .filter(|_| machine.has_audio_pattern());
 
-                    buffer[pos] = color;
-                    buffer[pos + 1] = color;
-                    buffer[pos + 2] = color;
-                }
+            if frame.sound {
+                device.resume();
+            } else {
+                device.pause()
             }
-        })?;
+        }
 
-        canvas.clear();
-        canvas.copy(&tex_display, None, None)?;
-        canvas.present();
+        // Skip the redraw when nothing changed, single steps don't go
+        // through advance so check the flag too
+        if display_changed || machine.take_display_dirty() || exposed {
+            // SUPER-CHIP ROMs can switch resolution at any time
+            if machine.display_dimensions() != tex_dimensions {
+                tex_dimensions = machine.display_dimensions();
+                tex_display = create_texture(tex_dimensions)?;
+            }
+
+            tex_display.with_lock(None, |buffer: &mut [u8], _pitch: usize| {
+                for (pixel, rgb) in machine.get_display().iter().zip(buffer.chunks_exact_mut(3)) {
+                    rgb.copy_from_slice(&options.palette.color(*pixel));
+                }
+            })?;
+
+            canvas.clear();
+            canvas.copy(&tex_display, None, None)?;
+            canvas.present();
+        }
 
-        std::thread::sleep(time::Duration::from_millis(15));
+        // Sleep off what is left of the frame budget, not at all when behind
+        std::thread::sleep(clock.until_next_frame().saturating_sub(now.elapsed()));
     }
 
-    Ok(())
+    input.finish()
 }
@@ -0,0 +1,169 @@
+//! Command line options
+
+use crate::keymap::{ButtonMap, Keymap};
+use crate::palette::Palette;
+use ::chip8::quirks::{Quirks, PRESETS};
+
+/// Instructions executed per 60Hz frame by default, about 600Hz
+const DEFAULT_CYCLES_PER_FRAME: usize = 10;
+
+/// Beeper pitch in Hz and amplitude by default
+const DEFAULT_TONE: f32 = 440.0;
+const DEFAULT_VOLUME: f32 = 0.25;
+
+pub const USAGE: &str = "Usage: chip8_sdl2 [options] [rom_file]
+
+Options:
+    --cycles N       instructions executed per 60Hz frame (default 10)
+    --seed N         seed for the random number generator
+    --record FILE    record key presses to FILE, F5 and single steps are off
+    --replay FILE    replay key presses from FILE, overrides --cycles, --seed,
+                     --quirks, --display-wait and --strict
+    --palette P      mono, amber, green, octo or hex colors like 000000,ffffff,
+                     XO-CHIP uses a third and fourth color for its second plane
+    --keys K0,...,KF SDL key names for CHIP-8 keys 0 to F
+    --buttons MAP    controller buttons like dpup=2,dpdown=8,a=5
+    --quirks NAME    interpreter to emulate: vip (default), chip48, schip or xo-chip,
+                     F5 cycles through them while running
+    --display-wait   draw at most one sprite per frame like the COSMAC VIP
+    --tone HZ        beeper pitch (default 440)
+    --volume V       beeper volume from 0 to 1 (default 0.25)
+    --strict         stop with an error when PC becomes odd
+    --headless       run without a window or sound and print the final state
+                     and memory, needs --frames, the seed defaults to 0
+    --frames N       frames to run in headless mode
+
+Hold Backspace to rewind up to 10 seconds, or press it while paused to go
+back one frame at a time.";
+
+/// Frontend configuration parsed from the command line
+pub struct Options {
+    pub rom: String,
+    pub cycles_per_frame: usize,
+    pub seed: Option<u64>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub palette: Palette,
+    pub keymap: Keymap,
+    pub buttons: ButtonMap,
+    pub quirks: Quirks,
+    pub tone: f32,
+    /// Always between 0 and 1, louder would clip
+    pub volume: f32,
+    pub strict: bool,
+    /// Frames to run without a window, set only with --headless
+    pub headless_frames: Option<usize>,
+}
+
+impl Options {
+    /// Parse the arguments, without the program name
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut rom = None;
+        let mut cycles_per_frame = DEFAULT_CYCLES_PER_FRAME;
+        let mut seed = None;
+        let mut record = None;
+        let mut replay = None;
+        let mut palette = Palette::default();
+        let mut keymap = Keymap::default();
+        let mut buttons = ButtonMap::default();
+        let mut quirks = Quirks::default();
+        let mut tone = DEFAULT_TONE;
+        let mut volume = DEFAULT_VOLUME;
+        let mut strict = false;
+        let mut display_wait = false;
+        let mut headless = false;
+        let mut frames = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--cycles" => {
+                    let value = args.next().ok_or("--cycles needs a value")?;
+                    cycles_per_frame = value
+                        .parse()
+                        .map_err(|_| format!("invalid cycle count: {}", value))?;
+                }
+                "--seed" => {
+                    let value = args.next().ok_or("--seed needs a value")?;
+                    seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid seed: {}", value))?,
+                    );
+                }
+                "--record" => record = Some(args.next().ok_or("--record needs a file")?),
+                "--replay" => replay = Some(args.next().ok_or("--replay needs a file")?),
+                "--palette" => {
+                    palette = Palette::parse(&args.next().ok_or("--palette needs a value")?)?
+                }
+                "--keys" => keymap = Keymap::parse(&args.next().ok_or("--keys needs a value")?)?,
+                "--buttons" => {
+                    buttons = ButtonMap::parse(&args.next().ok_or("--buttons needs a value")?)?
+                }
+                "--quirks" => {
+                    let name = args.next().ok_or("--quirks needs a value")?;
+                    quirks = PRESETS
+                        .iter()
+                        .find(|(preset, _)| *preset == name)
+                        .map(|(_, quirks)| *quirks)
+                        .ok_or_else(|| format!("unknown interpreter: {}", name))?;
+                }
+                "--tone" => {
+                    let value = args.next().ok_or("--tone needs a value")?;
+                    tone = value
+                        .parse()
+                        .ok()
+                        .filter(|hz: &f32| hz.is_finite() && *hz > 0.0)
+                        .ok_or_else(|| format!("invalid tone: {}", value))?;
+                }
+                "--volume" => {
+                    let value = args.next().ok_or("--volume needs a value")?;
+                    volume = value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|volume| !volume.is_nan())
+                        .ok_or_else(|| format!("invalid volume: {}", value))?
+                        .clamp(0.0, 1.0);
+                }
+                "--strict" => strict = true,
+                "--display-wait" => display_wait = true,
+                "--headless" => headless = true,
+                "--frames" => {
+                    let value = args.next().ok_or("--frames needs a value")?;
+                    frames = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid frame count: {}", value))?,
+                    );
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+                _ if rom.is_none() => rom = Some(arg),
+                _ => return Err(format!("unexpected argument: {}", arg)),
+            }
+        }
+
+        let headless_frames = match (headless, frames) {
+            (true, None) => return Err("--headless needs --frames".to_string()),
+            (false, Some(_)) => return Err("--frames only works with --headless".to_string()),
+            (_, frames) => frames,
+        };
+
+        // Applies to whichever preset was picked, regardless of order
+        quirks.display_wait = display_wait;
+
+        Ok(Options {
+            rom: rom.ok_or("missing rom_file")?,
+            cycles_per_frame,
+            seed,
+            record,
+            replay,
+            palette,
+            keymap,
+            buttons,
+            quirks,
+            tone,
+            volume,
+            strict,
+            headless_frames,
+        })
+    }
+}
@@ -0,0 +1,110 @@
+//! Display colors
+
+pub type Rgb = [u8; 3];
+
+/// Colors indexed by pixel value
+///
+/// Entry 0 is the background and entry 1 the foreground. XO-CHIP ROMs also
+/// use entry 2 for pixels lit only on the second plane and entry 3 for pixels
+/// lit on both. Values past the end use the last color.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    colors: Vec<Rgb>,
+}
+
+impl Palette {
+    /// Build a palette from at least a background and a foreground color
+    pub fn new(colors: Vec<Rgb>) -> Result<Self, String> {
+        if colors.len() < 2 {
+            return Err("a palette needs at least two colors".to_string());
+        }
+        Ok(Palette { colors })
+    }
+
+    /// One of the built-in palettes: `mono`, `amber`, `green` or `octo`, the
+    /// four XO-CHIP colors of the Octo IDE
+    pub fn named(name: &str) -> Option<Self> {
+        let colors = match name {
+            "mono" => vec![[0x00, 0x00, 0x00], [0xFF, 0xFF, 0xFF]],
+            "amber" => vec![[0x1A, 0x0F, 0x00], [0xFF, 0xB0, 0x00]],
+            "green" => vec![[0x00, 0x14, 0x00], [0x33, 0xFF, 0x33]],
+            "octo" => vec![
+                [0x99, 0x66, 0x00],
+                [0xFF, 0xCC, 0x00],
+                [0xFF, 0x66, 0x00],
+                [0x66, 0x22, 0x00],
+            ],
+            _ => return None,
+        };
+        Some(Palette { colors })
+    }
+
+    /// Parse a built-in name or comma separated hex colors, background first
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if let Some(palette) = Self::named(spec) {
+            return Ok(palette);
+        }
+
+        let colors = spec
+            .split(',')
+            .map(parse_hex)
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(colors)
+    }
+
+    /// The color for a pixel value
+    pub fn color(&self, pixel: u8) -> Rgb {
+        let last = self.colors.len() - 1;
+        self.colors[(pixel as usize).min(last)]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::named("mono").unwrap()
+    }
+}
+
+fn parse_hex(color: &str) -> Result<Rgb, String> {
+    let hex = color.trim().trim_start_matches('#');
+    let value = match hex.len() {
+        6 => u32::from_str_radix(hex, 16).ok(),
+        _ => None,
+    }
+    .ok_or_else(|| format!("invalid color: {}", color))?;
+
+    Ok([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_and_hex() {
+        assert_eq!(Palette::parse("mono").unwrap(), Palette::default());
+
+        let palette = Palette::parse("#102030,ffb000,00ff00").unwrap();
+        assert_eq!(palette.color(0), [0x10, 0x20, 0x30]);
+        assert_eq!(palette.color(1), [0xFF, 0xB0, 0x00]);
+        assert_eq!(palette.color(2), [0x00, 0xFF, 0x00]);
+        // Past the end falls back to the last entry
+        assert_eq!(palette.color(3), [0x00, 0xFF, 0x00]);
+    }
+
+    #[test]
+    fn octo_has_a_color_per_plane_combination() {
+        let palette = Palette::named("octo").unwrap();
+        let colors: Vec<Rgb> = (0..4).map(|pixel| palette.color(pixel)).collect();
+        for (i, color) in colors.iter().enumerate() {
+            assert!(!colors[..i].contains(color));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_palettes() {
+        assert!(Palette::parse("ffffff").is_err());
+        assert!(Palette::parse("000000,fff").is_err());
+        assert!(Palette::parse("purple").is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Deterministic input recording and playback
+//!
+//! A recording stores the RNG seed, the instructions per frame, the quirks,
+//! strict alignment and every key change tagged with the instruction count it
+//! happened at. Replaying it with the same ROM reproduces the original run
+//! exactly. Anything else that changes how the machine runs, single steps and
+//! switching presets, is disabled while recording or replaying.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+use ::chip8::chip8::Chip8;
+use ::chip8::io::Random;
+use ::chip8::quirks::{Quirks, PRESETS};
+
+const HEADER: &str = "chip8-replay 2";
+
+/// A key change at a given instruction count
+#[derive(Debug, PartialEq)]
+pub struct KeyEvent {
+    pub count: u64,
+    pub key: u8,
+    pub pressed: bool,
+}
+
+/// Everything needed to reproduce a run
+#[derive(Debug, PartialEq)]
+pub struct Recording {
+    pub seed: u64,
+    pub cycles_per_frame: usize,
+    /// One of the presets, with display wait on or off
+    pub quirks: Quirks,
+    pub strict: bool,
+    pub events: Vec<KeyEvent>,
+}
+
+/// Name of the preset `quirks` is, ignoring display wait
+fn preset_name(quirks: &Quirks) -> Option<&'static str> {
+    PRESETS
+        .iter()
+        .find(|(_, preset)| {
+            Quirks {
+                display_wait: quirks.display_wait,
+                ..*preset
+            } == *quirks
+        })
+        .map(|(name, _)| *name)
+}
+
+impl Recording {
+    /// Fails if `quirks` isn't one of the presets, only those can be saved
+    pub fn new(
+        seed: u64,
+        cycles_per_frame: usize,
+        quirks: Quirks,
+        strict: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        if preset_name(&quirks).is_none() {
+            return Err("only preset quirks can be recorded".into());
+        }
+        Ok(Recording {
+            seed,
+            cycles_per_frame,
+            quirks,
+            strict,
+            events: Vec::new(),
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        Ok(fs::write(path, self.to_string())?)
+    }
+
+    fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("not a chip8 replay file".into());
+        }
+
+        let mut field = |name: &str| -> Result<String, Box<dyn Error>> {
+            let line = lines.next().unwrap_or_default();
+            match line.split_once(' ') {
+                Some((key, value)) if key == name => Ok(value.to_string()),
+                _ => Err(format!("expected {}, got {:?}", name, line).into()),
+            }
+        };
+        let seed = field("seed")?.parse()?;
+        let cycles_per_frame = field("cycles")?.parse()?;
+        let name = field("quirks")?;
+        let mut quirks = PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .map(|(_, quirks)| *quirks)
+            .ok_or_else(|| format!("unknown quirks {:?}", name))?;
+        quirks.display_wait = field("display_wait")? == "1";
+        let strict = field("strict")? == "1";
+
+        let mut events = Vec::new();
+        for line in lines.filter(|l| !l.is_empty()) {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() != 3 {
+                return Err(format!("malformed event: {:?}", line).into());
+            }
+            events.push(KeyEvent {
+                count: parts[0].parse()?,
+                key: parts[1].parse()?,
+                pressed: parts[2] == "1",
+            });
+        }
+
+        Ok(Recording {
+            seed,
+            cycles_per_frame,
+            quirks,
+            strict,
+            events,
+        })
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "cycles {}", self.cycles_per_frame)?;
+        // `new` only accepts presets
+        let name = preset_name(&self.quirks).ok_or(fmt::Error)?;
+        writeln!(f, "quirks {}", name)?;
+        writeln!(f, "display_wait {}", self.quirks.display_wait as u8)?;
+        writeln!(f, "strict {}", self.strict as u8)?;
+        for event in &self.events {
+            writeln!(f, "{} {} {}", event.count, event.key, event.pressed as u8)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where key presses come from
+pub enum Input {
+    /// The keyboard
+    Live,
+    /// The keyboard, logging every change
+    Record { path: String, recording: Recording },
+    /// Recorded events, the keyboard is ignored
+    Replay { events: Vec<KeyEvent>, next: usize },
+}
+
+impl Input {
+    /// Recording or replaying, where only key changes may affect the run
+    pub fn is_deterministic(&self) -> bool {
+        !matches!(self, Input::Live)
+    }
+
+    /// Handle a key change from the keyboard
+    pub fn key<R: Random>(&mut self, machine: &mut Chip8<R>, key: u8, pressed: bool) {
+        match self {
+            Input::Live => {
+                machine.set_key(key, pressed);
+            }
+            Input::Record { recording, .. } => {
+                recording.events.push(KeyEvent {
+                    count: machine.instruction_count(),
+                    key,
+                    pressed,
+                });
+                machine.set_key(key, pressed);
+            }
+            Input::Replay { .. } => {}
+        }
+    }
+
+    /// Apply the recorded key changes due before the next frame
+    ///
+    /// Key changes are only recorded between frames, so with the same cycles
+    /// per frame they are replayed at exactly the same instruction.
+    pub fn before_frame<R: Random>(&mut self, machine: &mut Chip8<R>) {
+        if let Input::Replay { events, next } = self {
+            while let Some(event) = events.get(*next) {
+                if event.count > machine.instruction_count() {
+                    break;
+                }
+                machine.set_key(event.key, event.pressed);
+                *next += 1;
+            }
+        }
+    }
+
+    /// Save the recording, if any
+    pub fn finish(&self) -> Result<(), Box<dyn Error>> {
+        if let Input::Record { path, recording } = self {
+            recording.save(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_round_trips() {
+        let quirks = Quirks {
+            display_wait: true,
+            ..Quirks::schip()
+        };
+        let mut recording = Recording::new(42, 15, quirks, true).unwrap();
+        recording.events.push(KeyEvent {
+            count: 150,
+            key: 4,
+            pressed: true,
+        });
+        recording.events.push(KeyEvent {
+            count: 300,
+            key: 4,
+            pressed: false,
+        });
+
+        let text = recording.to_string();
+        assert_eq!(
+            text,
+            "chip8-replay 2\nseed 42\ncycles 15\nquirks schip\ndisplay_wait 1\nstrict 1\n\
+             150 4 1\n300 4 0\n"
+        );
+        assert_eq!(Recording::parse(&text).unwrap(), recording);
+    }
+
+    #[test]
+    fn rejects_foreign_files() {
+        assert!(Recording::parse("hello\n").is_err());
+        assert!(Recording::parse("chip8-replay 2\ncycles 10\n").is_err());
+        // Version 1 recordings don't have the quirks
+        assert!(Recording::parse("chip8-replay 1\nseed 1\ncycles 10\n").is_err());
+        assert!(Recording::parse(
+            "chip8-replay 2\nseed 1\ncycles 10\nquirks amiga\ndisplay_wait 0\nstrict 0\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn only_presets_can_be_recorded() {
+        let custom = Quirks {
+            wrap_sprites: true,
+            ..Quirks::cosmac_vip()
+        };
+        assert!(Recording::new(1, 10, custom, false).is_err());
+    }
+}
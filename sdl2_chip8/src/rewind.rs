@@ -0,0 +1,112 @@
+//! Rewinding through recently played frames
+//!
+//! Instead of a full snapshot per frame only the changes back to the previous
+//! frame are kept, which is usually a handful of bytes.
+
+use std::collections::VecDeque;
+
+use ::chip8::chip8::{Chip8, Snapshot, StateDelta};
+use ::chip8::io::Random;
+
+/// Frames kept by default, 10 seconds at 60Hz
+pub const REWIND_FRAMES: usize = 600;
+
+/// Bounded history of deltas that each take the machine back one frame
+pub struct Rewind {
+    capacity: usize,
+    /// The state the newest delta goes back from
+    current: Snapshot,
+    deltas: VecDeque<StateDelta>,
+}
+
+impl Rewind {
+    pub fn new<R: Random>(machine: &Chip8<R>, capacity: usize) -> Self {
+        Rewind {
+            capacity,
+            current: machine.snapshot(),
+            deltas: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record the machine state after a frame or step, dropping the oldest
+    /// frame once the buffer is full
+    pub fn push<R: Random>(&mut self, machine: &Chip8<R>) {
+        let snapshot = machine.snapshot();
+        let delta = self.current.diff(&snapshot);
+        self.current = snapshot;
+
+        if self.deltas.len() == self.capacity {
+            self.deltas.pop_front();
+        }
+        self.deltas.push_back(delta);
+    }
+
+    /// Take the machine back one recorded frame
+    ///
+    /// Returns false and leaves the machine alone at the start of the buffer.
+    /// The deltas all come from `machine`, so applying one doesn't fail.
+    pub fn rewind<R: Random>(&mut self, machine: &mut Chip8<R>) -> bool {
+        match self.deltas.pop_back() {
+            Some(delta) if machine.apply_delta(&delta).is_ok() => {
+                self.current = machine.snapshot();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::chip8::io::SeededRandom;
+
+    // LD V0, 0; ADD V0, 1; JP 202
+    const COUNTER: [u8; 6] = [0x60, 0x00, 0x70, 0x01, 0x12, 0x02];
+
+    fn machine() -> Chip8<SeededRandom> {
+        Chip8::new(&COUNTER, SeededRandom::new(0)).unwrap()
+    }
+
+    #[test]
+    fn rewinds_frame_by_frame() {
+        let mut machine = machine();
+        let mut rewind = Rewind::new(&machine, 10);
+        let mut states = vec![machine.snapshot()];
+        for _ in 0..3 {
+            machine.advance(4).unwrap();
+            rewind.push(&machine);
+            states.push(machine.snapshot());
+        }
+
+        states.pop();
+        while let Some(state) = states.pop() {
+            assert!(rewind.rewind(&mut machine));
+            assert_eq!(machine.snapshot(), state);
+        }
+    }
+
+    #[test]
+    fn stops_at_the_start_of_the_buffer() {
+        let mut machine = machine();
+        let mut rewind = Rewind::new(&machine, 2);
+        for _ in 0..5 {
+            machine.advance(4).unwrap();
+            rewind.push(&machine);
+        }
+
+        assert!(rewind.rewind(&mut machine));
+        assert!(rewind.rewind(&mut machine));
+        let oldest = machine.snapshot();
+        assert_eq!(machine.registers()[0], 6);
+
+        assert!(!rewind.rewind(&mut machine));
+        assert_eq!(machine.snapshot(), oldest);
+
+        // Recording continues from the rewound state
+        machine.advance(4).unwrap();
+        rewind.push(&machine);
+        assert!(rewind.rewind(&mut machine));
+        assert_eq!(machine.snapshot(), oldest);
+    }
+}
@@ -0,0 +1,45 @@
+//! PNG export of the display
+
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::palette::Palette;
+
+/// Write `display` to a PNG, each pixel scaled to a `scale` x `scale` block
+pub fn save_png(
+    path: &str,
+    display: &[u8],
+    (width, height): (usize, usize),
+    scale: usize,
+    palette: &Palette,
+) -> Result<(), Box<dyn Error>> {
+    let mut data = Vec::with_capacity(width * height * scale * scale * 3);
+    for row in display.chunks_exact(width).take(height) {
+        for _ in 0..scale {
+            for pixel in row {
+                let color = palette.color(*pixel);
+                for _ in 0..scale {
+                    data.extend_from_slice(&color);
+                }
+            }
+        }
+    }
+
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, (width * scale) as u32, (height * scale) as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&data)?;
+
+    Ok(())
+}
+
+/// A file name that doesn't clash with earlier screenshots
+pub fn timestamped_name() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("chip8-{}-{:03}.png", now.as_secs(), now.subsec_millis())
+}
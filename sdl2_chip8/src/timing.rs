@@ -0,0 +1,87 @@
+//! Fixed timestep clock for the emulation loop
+
+use std::time::Duration;
+
+/// Length of one 60Hz frame
+pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Most frames simulated by one `advance`, time beyond that is dropped so a
+/// machine that can't keep up doesn't fall further and further behind
+pub const MAX_CATCH_UP_FRAMES: u32 = 4;
+
+/// Accumulates real elapsed time and hands it out as whole 60Hz frames
+pub struct FrameClock {
+    accumulated: Duration,
+}
+
+impl FrameClock {
+    pub fn new() -> Self {
+        Self {
+            accumulated: Duration::ZERO,
+        }
+    }
+
+    /// Add elapsed real time, returns how many frames should be simulated
+    ///
+    /// The remainder is carried over to the next call. After a stall at most
+    /// `MAX_CATCH_UP_FRAMES` are returned and the rest of the backlog dropped.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulated += elapsed;
+
+        let mut frames = 0;
+        while self.accumulated >= FRAME {
+            self.accumulated -= FRAME;
+            frames += 1;
+        }
+        frames.min(MAX_CATCH_UP_FRAMES)
+    }
+
+    /// Time left until the next frame is due, for sleeping in between
+    pub fn until_next_frame(&self) -> Duration {
+        FRAME.saturating_sub(self.accumulated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_frames_are_simulated() {
+        let mut clock = FrameClock::new();
+
+        assert_eq!(clock.advance(Duration::ZERO), 0);
+        assert_eq!(clock.advance(FRAME), 1);
+        assert_eq!(clock.advance(FRAME * 3), 3);
+    }
+
+    #[test]
+    fn remainder_carries_over() {
+        let mut clock = FrameClock::new();
+        let half = FRAME / 2;
+
+        assert_eq!(clock.advance(half), 0);
+        assert_eq!(clock.advance(half + Duration::from_nanos(1)), 1);
+        assert_eq!(clock.advance(FRAME * 2 + half), 2);
+        assert_eq!(clock.advance(half), 1);
+    }
+
+    #[test]
+    fn catch_up_is_capped() {
+        let mut clock = FrameClock::new();
+
+        assert_eq!(clock.advance(FRAME * 100), MAX_CATCH_UP_FRAMES);
+        // The dropped backlog doesn't come back later
+        assert_eq!(clock.advance(Duration::ZERO), 0);
+        assert_eq!(clock.advance(FRAME), 1);
+    }
+
+    #[test]
+    fn sleeps_until_the_next_frame() {
+        let mut clock = FrameClock::new();
+        assert_eq!(clock.until_next_frame(), FRAME);
+
+        clock.advance(FRAME + FRAME / 4);
+        assert_eq!(clock.until_next_frame(), FRAME - FRAME / 4);
+    }
+}
@@ -0,0 +1,193 @@
+//! Terminal frontend, renders the display with Unicode half blocks
+//!
+//! Terminals only report key presses, so a pressed key is held for a short
+//! while and then released.
+
+mod screen;
+
+use std::error::Error;
+use std::io::{self, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+
+use chip8::chip8::Chip8;
+use chip8::io::SeededRandom;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+
+/// 60Hz
+const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+/// Instructions executed per frame, about 600Hz
+const CYCLES_PER_FRAME: usize = 10;
+/// How long a key stays pressed after the terminal reports it
+const KEY_HOLD: Duration = Duration::from_millis(150);
+
+/// Maps the left side of a QWERTY keyboard onto the keypad layout
+///
+/// ```text
+/// 1 2 3 4      1 2 3 C
+/// q w e r  ->  4 5 6 D
+/// a s d f      7 8 9 E
+/// z x c v      A 0 B F
+/// ```
+fn map_key(c: char) -> Option<u8> {
+    let key = match c.to_ascii_lowercase() {
+        '1' => 0x1,
+        '2' => 0x2,
+        '3' => 0x3,
+        '4' => 0xC,
+        'q' => 0x4,
+        'w' => 0x5,
+        'e' => 0x6,
+        'r' => 0xD,
+        'a' => 0x7,
+        's' => 0x8,
+        'd' => 0x9,
+        'f' => 0xE,
+        'z' => 0xA,
+        'x' => 0x0,
+        'c' => 0xB,
+        'v' => 0xF,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Puts the terminal into raw mode and restores it when dropped
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            println!("Usage: tui_chip8 [rom_file]");
+            std::process::exit(1);
+        }
+    };
+
+    let data = fs::read(&path).unwrap();
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+
+    let mut machine = match Chip8::new(&data, SeededRandom::new(seed)) {
+        Ok(machine) => machine,
+        Err(e) => {
+            println!("{}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+
+    let terminal = RawTerminal::enter().unwrap();
+    let result = run(&mut machine, &mut io::stdout());
+    // Restore the terminal before printing
+    drop(terminal);
+
+    if let Err(e) = result {
+        println!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(machine: &mut Chip8<SeededRandom>, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let mut released_at: [Option<Instant>; 16] = [None; 16];
+    let mut next_frame = Instant::now();
+    let mut clear = true;
+    let mut dimensions = machine.display_dimensions();
+
+    loop {
+        // Handle input until the next frame is due
+        let timeout = next_frame.saturating_duration_since(Instant::now());
+        if event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc, ..
+                }) => return Ok(()),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers,
+                    ..
+                }) if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(c),
+                    kind,
+                    ..
+                }) if kind != KeyEventKind::Release => {
+                    if let Some(key) = map_key(c) {
+                        machine.set_key(key, true);
+                        released_at[key as usize] = Some(Instant::now() + KEY_HOLD);
+                    }
+                }
+                // Re-center on the new size
+                Event::Resize(..) => clear = true,
+                _ => {}
+            }
+        }
+
+        // Checked after every event too, so a steady stream of key repeats
+        // doesn't hold the emulation back
+        let now = Instant::now();
+        if now < next_frame {
+            continue;
+        }
+        next_frame = (next_frame + FRAME).max(now);
+
+        for (key, release) in released_at.iter_mut().enumerate() {
+            if release.is_some_and(|at| at <= now) {
+                machine.set_key(key as u8, false);
+                *release = None;
+            }
+        }
+
+        machine.step_frame(CYCLES_PER_FRAME)?;
+
+        if machine.display_dimensions() != dimensions {
+            dimensions = machine.display_dimensions();
+            clear = true;
+        }
+        if machine.take_display_dirty() || clear {
+            draw(machine, out, clear)?;
+            clear = false;
+        }
+    }
+}
+
+/// Draw the display centered in the terminal
+fn draw(machine: &Chip8<SeededRandom>, out: &mut impl Write, clear: bool) -> io::Result<()> {
+    let (width, height) = machine.display_dimensions();
+    let lines = screen::lines(machine.get_display(), width, height);
+
+    let (columns, rows) = terminal::size()?;
+    let left = (columns as usize).saturating_sub(width) / 2;
+    let top = (rows as usize).saturating_sub(lines.len()) / 2;
+
+    if clear {
+        queue!(out, terminal::Clear(ClearType::All))?;
+    }
+    for (i, line) in lines.iter().enumerate() {
+        queue!(
+            out,
+            cursor::MoveTo(left as u16, (top + i) as u16),
+            Print(line)
+        )?;
+    }
+    out.flush()
+}
@@ -0,0 +1,41 @@
+//! Half-block rendering, two pixel rows per terminal row
+
+/// Render the display as text lines, one per two pixel rows
+pub fn lines(display: &[u8], width: usize, height: usize) -> Vec<String> {
+    let rows: Vec<&[u8]> = display.chunks_exact(width).take(height).collect();
+
+    rows.chunks(2)
+        .map(|pair| {
+            let top = pair[0];
+            let bottom = pair.get(1).copied().unwrap_or(&[]);
+            (0..width)
+                .map(|x| {
+                    let top = top[x] != 0;
+                    let bottom = bottom.get(x).is_some_and(|&pixel| pixel != 0);
+                    match (top, bottom) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_rows_into_half_blocks() {
+        let display = [
+            1, 1, 0, 0, //
+            1, 0, 1, 0, //
+            0, 0, 0, 1, //
+        ];
+
+        assert_eq!(lines(&display, 4, 3), vec!["█▀▄ ", "   ▀"]);
+    }
+}
@@ -0,0 +1,82 @@
+//! WebAssembly bindings for the CHIP-8 emulator
+//!
+//! Build with `wasm-pack build --target web` and serve the `www` directory
+//! together with the generated `pkg` directory.
+
+use chip8::chip8::Chip8;
+use chip8::io::Random;
+use wasm_bindgen::prelude::*;
+
+/// Random number generator backed by `Math.random()`
+struct JsRandom;
+
+impl Random for JsRandom {
+    fn randint(&mut self) -> u8 {
+        (js_sys::Math::random() * 256.0) as u8
+    }
+}
+
+/// CHIP-8 machine exposed to JavaScript
+#[wasm_bindgen]
+pub struct Emulator {
+    machine: Chip8<JsRandom>,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Build a machine from the ROM bytes in a `Uint8Array`
+    #[wasm_bindgen(constructor)]
+    pub fn new(rom: &[u8]) -> Result<Emulator, JsValue> {
+        let machine = Chip8::new(rom, JsRandom).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(Emulator { machine })
+    }
+
+    /// Execute up to `cycles` instructions, returns how many ran
+    ///
+    /// Stops early while the ROM waits for a key press.
+    pub fn step(&mut self, cycles: usize) -> Result<usize, JsValue> {
+        self.machine
+            .run_cycles(cycles)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Tick the 60Hz timers, returns true while the buzzer should sound
+    pub fn tick_timers(&mut self) -> bool {
+        let sound = self.machine.sound_active();
+        self.machine.tick_timers();
+        sound
+    }
+
+    /// Set the pressed state of a key, returns false for keys outside 0 to F
+    pub fn set_key(&mut self, key: u8, pressed: bool) -> bool {
+        self.machine.set_key(key, pressed)
+    }
+
+    /// Display width in pixels
+    pub fn width(&self) -> usize {
+        self.machine.display_dimensions().0
+    }
+
+    /// Display height in pixels
+    pub fn height(&self) -> usize {
+        self.machine.display_dimensions().1
+    }
+
+    /// Returns true if the display changed since the last call
+    pub fn take_display_dirty(&mut self) -> bool {
+        self.machine.take_display_dirty()
+    }
+
+    /// Copy of the framebuffer, one byte per pixel in row-major order
+    pub fn display(&self) -> Vec<u8> {
+        self.machine.get_display().to_vec()
+    }
+
+    /// Copy of the framebuffer, one bit per pixel, MSB first in row-major order
+    pub fn display_packed(&self) -> Vec<u8> {
+        let mut packed = vec![0; self.machine.get_display().len() / 8];
+        // The buffer is sized from the display, packing can't fail
+        let _ = self.machine.get_display_packed(&mut packed);
+        packed
+    }
+}
@@ -0,0 +1,105 @@
+import init, { Emulator } from "../pkg/wasm_chip8.js";
+
+// Instructions executed per 60Hz frame
+const CYCLES_PER_FRAME = 10;
+// Length of one 60Hz frame in milliseconds
+const FRAME_MS = 1000 / 60;
+// Most frames run per animation frame, the rest of a stall is dropped
+const MAX_CATCH_UP_FRAMES = 4;
+
+// Same layout as the SDL frontend
+const KEYMAP = {
+  Numpad7: 0x0, Numpad8: 0x1, Numpad9: 0x2,
+  Numpad4: 0x3, Numpad5: 0x4, Numpad6: 0x5,
+  Numpad1: 0x6, Numpad2: 0x7, Numpad3: 0x8,
+  KeyQ: 0x9, KeyW: 0xA, KeyE: 0xB,
+  KeyR: 0xC, KeyA: 0xD, KeyS: 0xE, KeyD: 0xF,
+};
+
+const canvas = document.getElementById("screen");
+const ctx = canvas.getContext("2d");
+const status = document.getElementById("status");
+let emulator = null;
+let running = false;
+// Real time not yet simulated, and when it was last measured
+let accumulated = 0;
+let lastTime = null;
+
+function draw() {
+  const width = emulator.width();
+  const height = emulator.height();
+  const pixels = emulator.display();
+  const image = ctx.createImageData(width, height);
+
+  for (let i = 0; i < pixels.length; i++) {
+    const color = pixels[i] ? 255 : 0;
+    image.data.set([color, color, color, 255], i * 4);
+  }
+  canvas.width = width;
+  canvas.height = height;
+  ctx.putImageData(image, 0, 0);
+}
+
+// Runs whole 60Hz frames for the time elapsed, independent of the
+// display's refresh rate
+function frame(now) {
+  if (lastTime !== null) {
+    accumulated += now - lastTime;
+  }
+  lastTime = now;
+
+  let frames = 0;
+  while (accumulated >= FRAME_MS) {
+    accumulated -= FRAME_MS;
+    frames++;
+  }
+
+  try {
+    for (let i = 0; i < Math.min(frames, MAX_CATCH_UP_FRAMES); i++) {
+      emulator.step(CYCLES_PER_FRAME);
+      emulator.tick_timers();
+    }
+  } catch (error) {
+    // Keep the last picture and wait for another ROM
+    status.textContent = `Stopped: ${error}`;
+    running = false;
+    return;
+  }
+
+  if (emulator.take_display_dirty()) {
+    draw();
+  }
+  requestAnimationFrame(frame);
+}
+
+function onKey(pressed) {
+  return (event) => {
+    const key = KEYMAP[event.code];
+    if (emulator && key !== undefined) {
+      emulator.set_key(key, pressed);
+    }
+  };
+}
+
+await init();
+
+document.addEventListener("keydown", onKey(true));
+document.addEventListener("keyup", onKey(false));
+document.getElementById("rom").addEventListener("change", async (event) => {
+  const file = event.target.files[0];
+  const rom = new Uint8Array(await file.arrayBuffer());
+
+  try {
+    emulator = new Emulator(rom);
+  } catch (error) {
+    alert(error);
+    return;
+  }
+  status.textContent = "";
+  accumulated = 0;
+  lastTime = null;
+  if (!running) {
+    running = true;
+    requestAnimationFrame(frame);
+  }
+});